@@ -23,8 +23,8 @@ pub mod prelude {
 
     // unsafe impl core::alloc::{...} for {...} => ialloc::fat::{...};
 
-    ( unsafe impl $([$($gdef:tt)*])? $(::)? core::alloc::GlobalAlloc for $ty:ty => $(::)? ialloc::fat::Realloc; $($tt:tt)* ) => {
-        unsafe impl $(<$($gdef)*>)? ::core::alloc::GlobalAlloc for $ty {
+    ( unsafe impl $([$($gdef:tt)*])? $(::)? core::alloc::GlobalAlloc for $ty:ty $(where [$($where:tt)*])? => $(::)? ialloc::fat::Realloc; $($tt:tt)* ) => {
+        unsafe impl $(<$($gdef)*>)? ::core::alloc::GlobalAlloc for $ty $(where $($where)*)? {
             #[track_caller] unsafe fn alloc(&self, layout: ::core::alloc::Layout) -> *mut ::core::primitive::u8 {
                 use $crate::_impls::prelude::*;
                 fat::Alloc::alloc_uninit(self, layout).map_or(null_mut(), |p| p.as_ptr().cast())
@@ -51,8 +51,8 @@ pub mod prelude {
         $crate::impls!($($tt)*);
     };
 
-    ( unsafe impl $([$($gdef:tt)*])? $(::)? core::alloc::Allocator(unstable $(1.50$(.0)?)?) for $ty:ty => $(::)? ialloc::fat::Realloc; $($tt:tt)* ) => {
-        unsafe impl $(<$($gdef)*>)? ::core::alloc::Allocator for $ty {
+    ( unsafe impl $([$($gdef:tt)*])? $(::)? core::alloc::Allocator(unstable $(1.50$(.0)?)?) for $ty:ty $(where [$($where:tt)*])? => $(::)? ialloc::fat::Realloc; $($tt:tt)* ) => {
+        unsafe impl $(<$($gdef)*>)? ::core::alloc::Allocator for $ty $(where $($where)*)? {
             #[track_caller] fn allocate(&self, layout: ::core::alloc::Layout) -> ::core::result::Result<::core::ptr::NonNull<[::core::primitive::u8]>, ::core::alloc::AllocError> {
                 use $crate::_impls::prelude::*;
                 let alloc = fat::Alloc::alloc_uninit(self, layout).map_err(|_| AllocError)?;
@@ -1,5 +1,12 @@
-//! [`AllocZst`], [`DangleZst`], [`PanicOverAlign`]
+//! [`AllocZst`], [`DangleZst`], [`Fallback`], [`Locked`], [`OverAlign`], [`PanicOverAlign`], [`Segregate`], [`SizeHeader`], [`ThreadCache`], [`ZeroOnFree`]
 
 mod alloc_zst;                  pub use alloc_zst::*;
 mod dangle_zst;                 pub use dangle_zst::*;
+mod fallback;                   pub use fallback::*;
+mod locked;                     pub use locked::*;
+mod over_align;                 pub use over_align::*;
 mod panic_over_align;           pub use panic_over_align::*;
+mod segregate;                  pub use segregate::*;
+mod size_header;                pub use size_header::*;
+#[cfg(feature = "std")] mod thread_cache;       #[cfg(feature = "std")] pub use thread_cache::*;
+mod zero_on_free;               pub use zero_on_free::*;
@@ -0,0 +1,195 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+
+
+
+/// Allocate from `Primary` where possible, falling back on `Secondary` when `Primary` fails or a request exceeds <code>Primary::[MAX_ALIGN](Meta::MAX_ALIGN)</code> / <code>[MAX_SIZE](Meta::MAX_SIZE)</code>.
+///
+/// `Primary` must implement [`Owns`], so frees and reallocations can be routed back to whichever allocator owns the pointer.
+/// Zero-sized allocations are dangling pointers owned by neither allocator.
+///
+/// ### Example
+/// ```rust
+/// # use ialloc::allocator::{adapt::Fallback, alloc::Global, simple::FixedPoolLinearProbe};
+/// # use ialloc::boxed::ABox;
+/// let pool = FixedPoolLinearProbe::<8, 8, 4>::new();
+/// let alloc = Fallback(&pool, Global);
+/// let small = ABox::new_in(1u64, &alloc);     // fits in `pool`
+/// let large = ABox::new_in([2u64; 4], &alloc); // too large for `pool`'s 8 byte elements:  falls back on `Global`
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)] pub struct Fallback<Primary, Secondary>(pub Primary, pub Secondary);
+
+impl<P: Meta, S: Meta> Fallback<P, S> {
+    fn primary_fits(layout: Layout) -> bool { layout.align() <= P::MAX_ALIGN.as_usize() && layout.size() <= P::MAX_SIZE }
+}
+
+
+
+// meta::*
+
+impl<P: Meta, S: Meta> Meta for Fallback<P, S> {
+    type Error                  = S::Error;
+    const MAX_ALIGN : Alignment = if P::MAX_ALIGN.as_usize() > S::MAX_ALIGN.as_usize() { P::MAX_ALIGN } else { S::MAX_ALIGN };
+    const MAX_SIZE  : usize     = if P::MAX_SIZE > S::MAX_SIZE { P::MAX_SIZE } else { S::MAX_SIZE };
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl<P: Meta, S: Meta> ZstSupported for Fallback<P, S> {}
+
+// SAFETY: ✔️ ZSTs are dangling pointers that never touch either allocator
+unsafe impl<P: Meta, S: Meta> ZstInfalliable for Fallback<P, S> {}
+
+// SAFETY: ✔️ per underlying allocators
+unsafe impl<P: Stateless, S: Stateless> Stateless for Fallback<P, S> {}
+
+// SAFETY: ✔️ every pointer owned by `self` is owned by one of the underlying allocators
+unsafe impl<P: Owns, S: Owns> Owns for Fallback<P, S> {
+    fn owns(&self, ptr: AllocNN) -> bool { self.0.owns(ptr) || self.1.owns(ptr) }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations come from either underlying allocator, or are dangling ZSTs
+//
+unsafe impl<P: fat::Alloc + Owns, S: fat::Alloc> fat::Alloc for Fallback<P, S> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> {
+        if layout.size() == 0 { return Ok(util::nn::dangling(layout)) }
+        if Self::primary_fits(layout) { if let Ok(alloc) = self.0.alloc_uninit(layout) { return Ok(alloc) } }
+        self.1.alloc_uninit(layout)
+    }
+
+    fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> {
+        if layout.size() == 0 { return Ok(util::nn::dangling(layout)) }
+        if Self::primary_fits(layout) { if let Ok(alloc) = self.0.alloc_zeroed(layout) { return Ok(alloc) } }
+        self.1.alloc_zeroed(layout)
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations are freed by whichever underlying allocator owns them
+//
+unsafe impl<P: fat::Free + Owns, S: fat::Free> fat::Free for Fallback<P, S> {
+    unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        if layout.size() == 0 { return }
+        // SAFETY: ✔️ `ptr` belongs to `self.0` or `self.1` per `fat::Free::free`'s documented safety preconditions, and `Owns` tells us which
+        if self.0.owns(ptr) { unsafe { self.0.free(ptr, layout) } } else { unsafe { self.1.free(ptr, layout) } }
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations are reallocated by whichever underlying allocator owns them, or moved from `Primary` to `Secondary`
+//
+unsafe impl<P: fat::Realloc + Owns, S: fat::Realloc> fat::Realloc for Fallback<P, S> {
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        if old_layout.size() == 0 { return fat::Alloc::alloc_uninit(self, new_layout) }
+        if new_layout.size() == 0 {
+            // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+            unsafe { fat::Free::free(self, ptr, old_layout) };
+            return Ok(util::nn::dangling(new_layout));
+        }
+
+        // SAFETY: ✔️ `ptr` belongs to `self.1` if not owned by `self.0`, and was allocated with `old_layout`, per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        if !self.0.owns(ptr) { return unsafe { self.1.realloc_uninit(ptr, old_layout, new_layout) } }
+
+        if Self::primary_fits(new_layout) {
+            // SAFETY: ✔️ `ptr` belongs to `self.0` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+            if let Ok(alloc) = unsafe { self.0.realloc_uninit(ptr, old_layout, new_layout) } { return Ok(alloc) }
+        }
+
+        // SAFETY: ✔️ `ptr` belongs to `self.0` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        unsafe { fat::realloc_by_copy(&self.0, &self.1, ptr, old_layout, new_layout) }
+    }
+}
+
+#[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, Fallback};
+    use crate::meta::Owns;
+
+    impls! {
+        unsafe impl[P: fat::Realloc + Owns, S: fat::Realloc] core::alloc::GlobalAlloc for Fallback<P, S> => ialloc::fat::Realloc;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl[P: fat::Realloc + Owns, S: fat::Realloc] core::alloc::Allocator(unstable 1.50) for Fallback<P, S> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(all(test, feature = "alloc"))] mod backing_global {
+    use super::*;
+    use crate::allocator::alloc::Global;
+    use crate::allocator::simple::FixedPoolLinearProbe;
+    use core::ptr::NonNull;
+
+    #[test] fn routing() {
+        use crate::boxed::ABox;
+
+        let pool = FixedPoolLinearProbe::<8, 8, 2>::new();
+        let alloc = Fallback(&pool, Global);
+
+        let a = ABox::try_new_in(1u64, &alloc).unwrap();
+        let b = ABox::try_new_in(2u64, &alloc).unwrap();
+        let c = ABox::try_new_in(3u64, &alloc).unwrap();        // `pool` exhausted
+        let d = ABox::try_new_in([4u64; 4], &alloc).unwrap();   // exceeds `pool`'s `MAX_SIZE`
+        assert!( pool.owns(NonNull::from(&*a).cast()));
+        assert!( pool.owns(NonNull::from(&*b).cast()));
+        assert!(!pool.owns(NonNull::from(&*c).cast()));
+        assert!(!pool.owns(NonNull::from(&*d).cast()));
+        drop(b);
+        let e = ABox::try_new_in(5u64, &alloc).unwrap();        // reuses `b`'s slot
+        assert!( pool.owns(NonNull::from(&*e).cast()));
+        assert_eq!((1, 3, [4; 4], 5), (*a, *c, *d, *e));
+    }
+
+    #[test] fn realloc_migrates() {
+        use crate::fat::*;
+
+        let pool = FixedPoolLinearProbe::<8, 8, 2>::new();
+        let alloc = Fallback(&pool, Global);
+        let l8  = Layout::new::<u64>();
+        let l32 = Layout::new::<[u64; 4]>();
+
+        let a = alloc.alloc_zeroed(l8).unwrap().cast();
+        assert!(pool.owns(a));
+        // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `l8`
+        let b = unsafe { alloc.realloc_zeroed(a, l8, l32) }.unwrap();
+        assert!(!pool.owns(b), "should've migrated to the secondary allocator");
+        // SAFETY: ✔️ `b` was just zeroed and is valid for `l32`
+        assert_eq!([0u64; 4], unsafe { b.cast::<[u64; 4]>().read() });
+        // SAFETY: ✔️ `b` belongs to `alloc` and was reallocated with `l32`
+        unsafe { alloc.free(b, l32) };
+    }
+
+    #[test] fn backing_allocators_as_primary() {
+        use crate::allocator::simple::{Arena, PoolFreeList, Slab};
+        use crate::boxed::ABox;
+
+        let arena = Arena::<Global>::with_chunk_size_in(Global, 256);
+        let pool  = PoolFreeList::<8, 8, 4, Global>::new();
+        let slab  = Slab::<&Arena<Global>>::new_in(&arena);
+
+        let a = [(); 64].map(|_| ABox::try_new_in(1u64, Fallback(&arena, Global)).unwrap()); // chunks keep growing
+        let p = [(); 16].map(|_| ABox::try_new_in(2u64, Fallback(&pool,  Global)).unwrap()); // pool keeps growing
+        let s = [(); 64].map(|_| ABox::try_new_in(3u64, Fallback(&slab,  Global)).unwrap());
+        let g = ABox::try_new_in([4u64; 4], Fallback(&pool, Global)).unwrap(); // exceeds `pool`'s `MAX_SIZE`
+
+        assert!(a.iter().all(|a| arena.owns(NonNull::from(&**a).cast())));
+        assert!(p.iter().all(|p| pool .owns(NonNull::from(&**p).cast())));
+        assert!(s.iter().all(|s| slab .owns(NonNull::from(&**s).cast())));
+        assert!(!pool.owns(NonNull::from(&*g).cast()));
+        assert!(!arena.owns(NonNull::from(&*p[0]).cast()));
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(Fallback(&FixedPoolLinearProbe::<8, 8, 64>::new(), Global)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(Fallback(&FixedPoolLinearProbe::<8, 8, 64>::new(), Global)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(Fallback(&FixedPoolLinearProbe::<8, 8, 64>::new(), Global)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(Fallback(&FixedPoolLinearProbe::<8, 8, 64>::new(), Global)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(Fallback(&FixedPoolLinearProbe::<8, 8, 64>::new(), Global)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(Fallback(&FixedPoolLinearProbe::<8, 8, 64>::new(), Global)) }
+}
@@ -0,0 +1,274 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::cell::UnsafeCell;
+use core::fmt::{self, Debug, Formatter};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+
+
+/// Make a single-threaded allocator [`Sync`] by serializing all access to it behind a spin lock.
+///
+/// All [`meta`], [`thin`], and [`fat`] traits are forwarded from <code>&[Locked]&lt;A&gt;</code> to `&A`.
+/// As with [`simple::FixedPoolLinearProbe`](crate::allocator::simple::FixedPoolLinearProbe), the traits are implemented on shared references:
+/// this lets `A` keep its storage inline, without allocations being invalidated when the allocator is moved.
+///
+/// The lock is a `no_std` friendly spin lock.
+/// With the `std` feature, [`MutexLocked`] uses a [`std::sync::Mutex`] instead, which will put waiting threads to sleep rather than spinning.
+///
+/// ### Example
+/// ```rust
+/// # use ialloc::allocator::{adapt::Locked, simple::FixedPoolLinearProbe};
+/// # use ialloc::boxed::ABox;
+/// let pool = Locked::new(FixedPoolLinearProbe::<8, 8, 64>::new());
+/// std::thread::scope(|s| for i in 0 .. 4 {
+///     let pool = &pool;
+///     s.spawn(move || { let _ = ABox::new_in(i as u64, pool); });
+/// });
+/// ```
+pub struct Locked<A> {
+    locked:     AtomicBool,
+    allocator:  UnsafeCell<A>,
+}
+
+// SAFETY: ✔️ `Locked` owns an `A`, which it only hands out references to on one thread at a time
+unsafe impl<A: Send> Send for Locked<A> {}
+
+// SAFETY: ✔️ all access to `A` is serialized by `locked`, so `&Locked<A>` only ever requires moving access to `A` between threads
+unsafe impl<A: Send> Sync for Locked<A> {}
+
+impl<A> Locked<A> {
+    /// Wrap `allocator` in a spin lock.
+    pub const fn new(allocator: A) -> Self { Self { locked: AtomicBool::new(false), allocator: UnsafeCell::new(allocator) } }
+
+    /// Unwrap the underlying allocator.
+    pub fn into_inner(self) -> A { self.allocator.into_inner() }
+
+    /// Access the underlying allocator without locking, as `&mut self` guarantees exclusive access.
+    pub fn get_mut(&mut self) -> &mut A { self.allocator.get_mut() }
+
+    /// Run `f` with exclusive access to the underlying allocator.
+    ///
+    /// ### Safety
+    /// *   `f` must not let the `&'a A` it's given escape (e.g. via it's return value)
+    pub(crate) unsafe fn with<'a, R>(&'a self, f: impl FnOnce(&'a A) -> R) -> R {
+        struct Unlock<'a>(&'a AtomicBool);
+        impl Drop for Unlock<'_> { fn drop(&mut self) { self.0.store(false, Ordering::Release) } }
+
+        while self.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            while self.locked.load(Ordering::Relaxed) { core::hint::spin_loop() }
+        }
+        let _unlock = Unlock(&self.locked); // unlock even if `A` panics
+        // SAFETY: ✔️ `locked` is held until `_unlock` is dropped, and `f` won't let the reference escape per the fn's documented safety preconditions
+        f(unsafe { &*self.allocator.get() })
+    }
+}
+
+impl<A: Default> Default for Locked<A> { fn default() -> Self { Self::new(A::default()) } }
+impl<A> Debug for Locked<A> { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { fmt.debug_struct("Locked").finish_non_exhaustive() } }
+impl<A> From<A> for Locked<A> { fn from(allocator: A) -> Self { Self::new(allocator) } }
+
+
+
+/// Make a single-threaded allocator [`Sync`] by serializing all access to it behind a [`std::sync::Mutex`].
+///
+/// Identical to [`Locked`], except waiting threads sleep instead of spinning.
+/// Poisoning is ignored: `A` is never left mid-operation by the panics this crate uses to report bugs.
+#[cfg(feature = "std")] pub struct MutexLocked<A>(std::sync::Mutex<A>);
+
+#[cfg(feature = "std")] impl<A> MutexLocked<A> {
+    /// Wrap `allocator` in a [`std::sync::Mutex`].
+    pub const fn new(allocator: A) -> Self { Self(std::sync::Mutex::new(allocator)) }
+
+    /// Unwrap the underlying allocator.
+    pub fn into_inner(self) -> A { self.0.into_inner().unwrap_or_else(std::sync::PoisonError::into_inner) }
+
+    /// Access the underlying allocator without locking, as `&mut self` guarantees exclusive access.
+    pub fn get_mut(&mut self) -> &mut A { self.0.get_mut().unwrap_or_else(std::sync::PoisonError::into_inner) }
+
+    /// Run `f` with exclusive access to the underlying allocator.
+    ///
+    /// ### Safety
+    /// *   `f` must not let the `&'a A` it's given escape (e.g. via it's return value)
+    unsafe fn with<'a, R>(&'a self, f: impl FnOnce(&'a A) -> R) -> R {
+        let guard = self.0.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        // SAFETY: ✔️ `guard` is held until `f` returns, and `f` won't let the reference escape per the fn's documented safety preconditions
+        f(unsafe { &*(&*guard as *const A) })
+    }
+}
+
+#[cfg(feature = "std")] impl<A: Default> Default for MutexLocked<A> { fn default() -> Self { Self::new(A::default()) } }
+#[cfg(feature = "std")] impl<A> Debug for MutexLocked<A> { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { fmt.debug_struct("MutexLocked").finish_non_exhaustive() } }
+#[cfg(feature = "std")] impl<A> From<A> for MutexLocked<A> { fn from(allocator: A) -> Self { Self::new(allocator) } }
+
+
+
+macro_rules! forward_locked {
+    ( $(#[$attr:meta])* $locked:ident ) => {
+        // meta::*
+
+        $(#[$attr])* impl<'a, A> Meta for &'a $locked<A> where &'a A: Meta {
+            type Error                  = <&'a A as Meta>::Error;
+            const MAX_ALIGN : Alignment = <&'a A as Meta>::MAX_ALIGN;
+            const MAX_SIZE  : usize     = <&'a A as Meta>::MAX_SIZE;
+            const ZST_SUPPORTED : bool  = <&'a A as Meta>::ZST_SUPPORTED;
+        }
+
+        $(#[$attr])* impl<'a, A> ZstSupported for &'a $locked<A> where &'a A: ZstSupported {}
+
+        // SAFETY: ✔️ per underlying allocator
+        $(#[$attr])* unsafe impl<'a, A> ZstInfalliable for &'a $locked<A> where &'a A: ZstInfalliable {}
+
+        // SAFETY: ✔️ per underlying allocator
+        $(#[$attr])* unsafe impl<'a, A> Owns for &'a $locked<A> where &'a A: Owns {
+            // SAFETY: ✔️ `a` doesn't escape
+            fn owns(&self, ptr: AllocNN) -> bool { unsafe { $locked::with(*self, |a| a.owns(ptr)) } }
+        }
+
+
+
+        // thin::*
+
+        #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs, and `a` never escapes `with`
+        $(#[$attr])* unsafe impl<'a, A> thin::Alloc for &'a $locked<A> where &'a A: thin::Alloc {
+            fn alloc_uninit(&self, size: usize) -> Result<AllocNN,  Self::Error> { unsafe { $locked::with(*self, |a| thin::Alloc::alloc_uninit(&a, size)) } }
+            fn alloc_zeroed(&self, size: usize) -> Result<AllocNN0, Self::Error> { unsafe { $locked::with(*self, |a| thin::Alloc::alloc_zeroed(&a, size)) } }
+        }
+
+        #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs, and `a` never escapes `with`
+        $(#[$attr])* unsafe impl<'a, A> thin::Free for &'a $locked<A> where &'a A: thin::Free {
+            unsafe fn free(&self, ptr: AllocNN) { unsafe { $locked::with(*self, |a| thin::Free::free(&a, ptr)) } }
+            unsafe fn free_nullable(&self, ptr: *mut core::mem::MaybeUninit<u8>) { unsafe { $locked::with(*self, |a| thin::Free::free_nullable(&a, ptr)) } }
+        }
+
+        #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs, and `a` never escapes `with`
+        $(#[$attr])* unsafe impl<'a, A> thin::Realloc for &'a $locked<A> where &'a A: thin::Realloc {
+            const CAN_REALLOC_ZEROED : bool = <&'a A as thin::Realloc>::CAN_REALLOC_ZEROED;
+            unsafe fn realloc_uninit(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> { unsafe { $locked::with(*self, |a| thin::Realloc::realloc_uninit(&a, ptr, new_size)) } }
+            unsafe fn realloc_zeroed(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> { unsafe { $locked::with(*self, |a| thin::Realloc::realloc_zeroed(&a, ptr, new_size)) } }
+        }
+
+        #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs, and `a` never escapes `with`
+        $(#[$attr])* unsafe impl<'a, A> thin::SizeOf for &'a $locked<A> where &'a A: thin::SizeOf {
+            unsafe fn size_of(&self, ptr: AllocNN) -> usize { unsafe { $locked::with(*self, |a| thin::SizeOf::size_of(&a, ptr)) } }
+        }
+
+        #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs, and `a` never escapes `with`
+        $(#[$attr])* unsafe impl<'a, A> thin::SizeOfDebug for &'a $locked<A> where &'a A: thin::SizeOfDebug {
+            unsafe fn size_of_debug(&self, ptr: AllocNN) -> Option<usize> { unsafe { $locked::with(*self, |a| thin::SizeOfDebug::size_of_debug(&a, ptr)) } }
+        }
+
+
+
+        // fat::*
+
+        #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs, and `a` never escapes `with`
+        $(#[$attr])* unsafe impl<'a, A> fat::Alloc for &'a $locked<A> where &'a A: fat::Alloc {
+            fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN,  Self::Error> { unsafe { $locked::with(*self, |a| fat::Alloc::alloc_uninit(&a, layout)) } }
+            fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> { unsafe { $locked::with(*self, |a| fat::Alloc::alloc_zeroed(&a, layout)) } }
+        }
+
+        #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs, and `a` never escapes `with`
+        $(#[$attr])* unsafe impl<'a, A> fat::Free for &'a $locked<A> where &'a A: fat::Free {
+            unsafe fn free(&self, ptr: AllocNN, layout: Layout) { unsafe { $locked::with(*self, |a| fat::Free::free(&a, ptr, layout)) } }
+        }
+
+        #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs, and `a` never escapes `with`
+        $(#[$attr])* unsafe impl<'a, A> fat::Realloc for &'a $locked<A> where &'a A: fat::Realloc {
+            unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> { unsafe { $locked::with(*self, |a| fat::Realloc::realloc_uninit(&a, ptr, old_layout, new_layout)) } }
+            unsafe fn realloc_zeroed(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> { unsafe { $locked::with(*self, |a| fat::Realloc::realloc_zeroed(&a, ptr, old_layout, new_layout)) } }
+        }
+    };
+}
+
+forward_locked!(Locked);
+forward_locked!(#[cfg(feature = "std")] MutexLocked);
+
+#[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, Locked};
+    #[cfg(feature = "std")] use super::MutexLocked;
+
+    impls! {
+        unsafe impl['a, A] core::alloc::GlobalAlloc for &'a Locked<A> where [&'a A: fat::Realloc] => ialloc::fat::Realloc;
+    }
+
+    #[cfg(feature = "std")] impls! {
+        unsafe impl['a, A] core::alloc::GlobalAlloc for &'a MutexLocked<A> where [&'a A: fat::Realloc] => ialloc::fat::Realloc;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl['a, A] core::alloc::Allocator(unstable 1.50) for &'a Locked<A> where [&'a A: fat::Realloc] => ialloc::fat::Realloc;
+    }
+
+    #[cfg(all(feature = "std", allocator_api = "1.50"))] impls! {
+        unsafe impl['a, A] core::alloc::Allocator(unstable 1.50) for &'a MutexLocked<A> where [&'a A: fat::Realloc] => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+    use crate::allocator::simple::{Bump, FixedPoolLinearProbe};
+    use core::mem::MaybeUninit;
+
+    type Pool = FixedPoolLinearProbe<8, 16, 64>;
+
+    #[test] fn global_alloc() {
+        use core::alloc::GlobalAlloc;
+        let pool = Locked::new(Pool::new());
+        let layout = Layout::new::<u64>();
+        // SAFETY: ✔️ `layout` is non-zero sized
+        let ptr = unsafe { (&pool).alloc(layout) };
+        assert!(!ptr.is_null());
+        // SAFETY: ✔️ `ptr` was just allocated by `&pool` with `layout`
+        unsafe { (&pool).dealloc(ptr, layout) };
+    }
+
+    #[cfg(feature = "std")] #[test] fn threads() {
+        use crate::boxed::ABox;
+        let pool = Locked::new(Pool::new());
+        std::thread::scope(|s| for t in 0 .. 4 {
+            let pool = &pool;
+            s.spawn(move || for i in 0 .. 1000 {
+                let a = ABox::try_new_in(t * 1000 + i, pool).unwrap();
+                let b = ABox::try_new_in(!(t * 1000 + i), pool).unwrap();
+                assert_eq!((t * 1000 + i, !(t * 1000 + i)), (*a, *b));
+            });
+        });
+    }
+
+    #[test] fn thin_alignment()         { thin::test::alignment(&Locked::new(Pool::new())) }
+    #[test] fn thin_nullable()          { thin::test::nullable(&Locked::new(Pool::new())) }
+    #[test] fn thin_zeroed()            { thin::test::zeroed_alloc(&Locked::new(Pool::new())) }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(&Locked::new(Pool::new())) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(&Locked::new(Pool::new())) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(&Locked::new(Bump::new(&mut [MaybeUninit::uninit(); 4096][..]))) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(&Locked::new(Pool::new())) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(&Locked::new(Bump::new(&mut [MaybeUninit::uninit(); 4096][..]))) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_conservative(&Locked::new(Pool::new())) }
+
+    #[cfg(feature = "std")] mod mutex {
+        use super::*;
+
+        #[test] fn threads() {
+            use crate::boxed::ABox;
+            let pool = MutexLocked::new(Pool::new());
+            std::thread::scope(|s| for t in 0 .. 4 {
+                let pool = &pool;
+                s.spawn(move || for i in 0 .. 1000 {
+                    let a = ABox::try_new_in(t * 1000 + i, pool).unwrap();
+                    assert_eq!(t * 1000 + i, *a);
+                });
+            });
+        }
+
+        #[test] fn fat_alignment()          { fat::test::alignment(&MutexLocked::new(Pool::new())) }
+        #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(&MutexLocked::new(Pool::new())) }
+        #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(&MutexLocked::new(Bump::new(&mut [MaybeUninit::uninit(); 4096][..]))) }
+        #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(&MutexLocked::new(Pool::new())) }
+        #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(&MutexLocked::new(Bump::new(&mut [MaybeUninit::uninit(); 4096][..]))) }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::*;
+use crate::error::ExcessiveSliceRequestedError;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::mem::size_of;
+use core::ptr::NonNull;
+
+
+
+/// Adapt a [`thin`] allocator to a [`fat`] interface, supporting alignments larger than [`Meta::MAX_ALIGN`].
+///
+/// Allocations with more alignment than `A` supports are over-allocated from `A`, and the pointer is aligned forward.
+/// The offset back to `A`'s allocation is kept in a hidden `usize` header immediately before the returned pointer.
+/// Allocations `A` can align natively are passed through untouched, without a header.
+///
+/// ### Example
+/// ```rust
+/// # #[cfg(c89)] {
+/// # use ialloc::allocator::{adapt::OverAlign, c::Malloc};
+/// # use ialloc::boxed::ABox;
+/// #[repr(C, align(4096))] struct Page([u8; 4096]);
+/// let page = ABox::new_in(Page([0u8; 4096]), OverAlign(Malloc));
+/// assert_eq!(0, (&*page as *const Page as usize) % 4096);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)] #[repr(transparent)] pub struct OverAlign<A>(pub A);
+
+impl<A: Meta> OverAlign<A> {
+    fn is_over_aligned(layout: Layout) -> bool { layout.align() > A::MAX_ALIGN.as_usize() }
+
+    /// The number of bytes to allocate from `A` to fit an over-aligned `layout` and its header.
+    fn over_allocation_size(layout: Layout) -> Result<usize, A::Error> {
+        layout.size().checked_add(layout.align()).and_then(|n| n.checked_add(size_of::<usize>()))
+            .ok_or_else(|| ExcessiveSliceRequestedError { requested: layout.size() }.into())
+    }
+
+    /// Align `raw` (an allocation of [`over_allocation_size`](Self::over_allocation_size) bytes) for `layout`, and write the header.
+    ///
+    /// ### Safety
+    /// *   `raw` must be an allocation of at least <code>[over_allocation_size](Self::over_allocation_size)\(layout\)</code> bytes.
+    unsafe fn align_and_write_header(raw: AllocNN, layout: Layout) -> AllocNN {
+        let raw_addr = raw.as_ptr() as usize;
+        let align_mask = layout.align() - 1;
+        let offset = (raw_addr.wrapping_add(size_of::<usize>() + align_mask) & !align_mask).wrapping_sub(raw_addr);
+        // SAFETY: ✔️ `size_of::<usize>() <= offset < size_of::<usize>() + align`, so both the header and `layout.size()` bytes after it fit within `raw`
+        unsafe {
+            let aligned = raw.as_ptr().add(offset);
+            aligned.sub(size_of::<usize>()).cast::<usize>().write_unaligned(offset);
+            NonNull::new_unchecked(aligned)
+        }
+    }
+
+    /// Recover the allocation of `A` that `ptr` was aligned within.
+    ///
+    /// ### Safety
+    /// *   `ptr` must have been returned by [`align_and_write_header`](Self::align_and_write_header).
+    unsafe fn raw_of(ptr: AllocNN) -> AllocNN {
+        // SAFETY: ✔️ the header immediately precedes `ptr`, and `raw` precedes it by the offset it stores, within the same allocation
+        unsafe {
+            let offset = ptr.as_ptr().sub(size_of::<usize>()).cast::<usize>().read_unaligned();
+            NonNull::new_unchecked(ptr.as_ptr().sub(offset))
+        }
+    }
+}
+
+
+
+// meta::*
+
+impl<A: Meta> Meta for OverAlign<A> {
+    type Error                  = A::Error;
+    const MAX_ALIGN : Alignment = Alignment::MAX;
+    const MAX_SIZE  : usize     = A::MAX_SIZE;
+    const ZST_SUPPORTED : bool  = A::ZST_SUPPORTED;
+}
+
+impl<A: ZstSupported> ZstSupported for OverAlign<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: Stateless> Stateless for OverAlign<A> {}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ over-aligned allocations are aligned within an over-allocation from `A`, others come straight from `A`
+//
+unsafe impl<A: thin::Alloc> fat::Alloc for OverAlign<A> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> {
+        if !Self::is_over_aligned(layout) { return self.0.alloc_uninit(layout.size()) }
+        let raw = self.0.alloc_uninit(Self::over_allocation_size(layout)?)?;
+        // SAFETY: ✔️ `raw` was just allocated with `over_allocation_size(layout)` bytes
+        Ok(unsafe { Self::align_and_write_header(raw, layout) })
+    }
+
+    fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> {
+        if !Self::is_over_aligned(layout) { return self.0.alloc_zeroed(layout.size()) }
+        let raw = self.0.alloc_zeroed(Self::over_allocation_size(layout)?)?;
+        // SAFETY: ✔️ `raw` was just allocated with `over_allocation_size(layout)` bytes, all zeroed - the header is written before the zeroed payload
+        Ok(unsafe { Self::align_and_write_header(raw.cast(), layout) }.cast())
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ over-aligned allocations free the over-allocation they were aligned within
+//
+unsafe impl<A: thin::Free> fat::Free for OverAlign<A> {
+    unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        // SAFETY: ✔️ `ptr` was allocated by `self` with `layout` per `fat::Free::free`'s documented safety preconditions, so it has a header iff over-aligned
+        let raw = if Self::is_over_aligned(layout) { unsafe { Self::raw_of(ptr) } } else { ptr };
+        // SAFETY: ✔️ `raw` was allocated by `self.0`
+        unsafe { self.0.free(raw) }
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations that are over-aligned before or after are moved, others are reallocated by `A`
+//
+unsafe impl<A: thin::Realloc> fat::Realloc for OverAlign<A> {
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        if old_layout == new_layout { return Ok(ptr) }
+        if !Self::is_over_aligned(old_layout) && !Self::is_over_aligned(new_layout) {
+            // SAFETY: ✔️ `ptr` was allocated by `self.0` without a header per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+            return unsafe { self.0.realloc_uninit(ptr, new_layout.size()) };
+        }
+
+        // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        unsafe { fat::realloc_by_copy(self, self, ptr, old_layout, new_layout) }
+    }
+}
+
+#[no_implicit_prelude] mod cleanroom {
+    use super::{impls, thin, OverAlign};
+
+    impls! {
+        unsafe impl[A: thin::Realloc] core::alloc::GlobalAlloc for OverAlign<A> => ialloc::fat::Realloc;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl[A: thin::Realloc] core::alloc::Allocator(unstable 1.50) for OverAlign<A> => ialloc::fat::Realloc;
+    }
+}
@@ -0,0 +1,191 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+
+
+
+/// Allocate up to `THRESHOLD` bytes from `Small`, and anything larger from `Large`.
+///
+/// Allocations which exceed <code>Small::[MAX_ALIGN](Meta::MAX_ALIGN)</code> or <code>Small::[MAX_SIZE](Meta::MAX_SIZE)</code> are also sent to `Large`.
+/// Both allocators must share the same [`Meta::Error`] type.
+/// As the allocator is picked from the [`Layout`] alone, frees need no ownership query, and reallocations across the threshold move the data between allocators.
+///
+/// ### Example
+/// ```rust
+/// # use ialloc::allocator::{adapt::Segregate, alloc::Global, simple::Slab};
+/// # use ialloc::boxed::ABox;
+/// let slab = Slab::<Global>::new();
+/// let alloc = Segregate::<{Slab::<Global>::MAX_CLASS}, _, _>(&slab, Global);
+/// let small = ABox::new_in(1u64, &alloc);             // from `slab`
+/// let large = ABox::new_in([2u64; 1024], &alloc);     // from `Global`
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)] pub struct Segregate<const THRESHOLD: usize, Small, Large>(pub Small, pub Large);
+
+impl<const THRESHOLD: usize, S: Meta, L: Meta> Segregate<THRESHOLD, S, L> {
+    fn is_small(layout: Layout) -> bool { layout.size() <= THRESHOLD && layout.size() <= S::MAX_SIZE && layout.align() <= S::MAX_ALIGN.as_usize() }
+}
+
+
+
+// meta::*
+
+impl<const THRESHOLD: usize, S: Meta, L: Meta> Meta for Segregate<THRESHOLD, S, L> {
+    type Error                  = L::Error;
+    const MAX_ALIGN : Alignment = if S::MAX_ALIGN.as_usize() > L::MAX_ALIGN.as_usize() { S::MAX_ALIGN } else { L::MAX_ALIGN };
+    const MAX_SIZE  : usize     = { let small = if THRESHOLD < S::MAX_SIZE { THRESHOLD } else { S::MAX_SIZE }; if small > L::MAX_SIZE { small } else { L::MAX_SIZE } };
+    const ZST_SUPPORTED : bool  = S::ZST_SUPPORTED && L::ZST_SUPPORTED;
+}
+
+impl<const THRESHOLD: usize, S: ZstSupported, L: ZstSupported> ZstSupported for Segregate<THRESHOLD, S, L> {}
+
+// SAFETY: ✔️ ZSTs are routed to `Small`, or `Large` if overaligned - both of which are `ZstInfalliable`
+unsafe impl<const THRESHOLD: usize, S: ZstInfalliable, L: ZstInfalliable> ZstInfalliable for Segregate<THRESHOLD, S, L> {}
+
+// SAFETY: ✔️ per underlying allocators
+unsafe impl<const THRESHOLD: usize, S: Stateless, L: Stateless> Stateless for Segregate<THRESHOLD, S, L> {}
+
+// SAFETY: ✔️ every pointer owned by `self` is owned by one of the underlying allocators
+unsafe impl<const THRESHOLD: usize, S: Owns, L: Owns> Owns for Segregate<THRESHOLD, S, L> {
+    fn owns(&self, ptr: AllocNN) -> bool { self.0.owns(ptr) || self.1.owns(ptr) }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations come from whichever underlying allocator `layout` is routed to
+//
+unsafe impl<const THRESHOLD: usize, S: fat::Alloc<Error = L::Error>, L: fat::Alloc> fat::Alloc for Segregate<THRESHOLD, S, L> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> {
+        if Self::is_small(layout) { self.0.alloc_uninit(layout) } else { self.1.alloc_uninit(layout) }
+    }
+
+    fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> {
+        if Self::is_small(layout) { self.0.alloc_zeroed(layout) } else { self.1.alloc_zeroed(layout) }
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations are freed by whichever underlying allocator `layout` was routed to
+//
+unsafe impl<const THRESHOLD: usize, S: fat::Free<Error = L::Error>, L: fat::Free> fat::Free for Segregate<THRESHOLD, S, L> {
+    unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        // SAFETY: ✔️ `ptr` was allocated with `layout` per `fat::Free::free`'s documented safety preconditions, which was routed to the same allocator
+        if Self::is_small(layout) { unsafe { self.0.free(ptr, layout) } } else { unsafe { self.1.free(ptr, layout) } }
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations are reallocated by whichever underlying allocator `old_layout` was routed to, or moved to the allocator `new_layout` is routed to
+//
+unsafe impl<const THRESHOLD: usize, S: fat::Realloc<Error = L::Error>, L: fat::Realloc> fat::Realloc for Segregate<THRESHOLD, S, L> {
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        match (Self::is_small(old_layout), Self::is_small(new_layout)) {
+            // SAFETY: ✔️ `ptr` was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions, which was routed to the same allocator
+            (true,  true ) => return unsafe { self.0.realloc_uninit(ptr, old_layout, new_layout) },
+            // SAFETY: ✔️ `ptr` was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions, which was routed to the same allocator
+            (false, false) => return unsafe { self.1.realloc_uninit(ptr, old_layout, new_layout) },
+            _ => {},
+        }
+
+        // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        unsafe { fat::realloc_by_copy(self, self, ptr, old_layout, new_layout) }
+    }
+}
+
+#[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, Segregate};
+
+    impls! {
+        unsafe impl[const THRESHOLD: usize, S: fat::Realloc<Error = L::Error>, L: fat::Realloc] core::alloc::GlobalAlloc for Segregate<THRESHOLD, S, L> => ialloc::fat::Realloc;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl[const THRESHOLD: usize, S: fat::Realloc<Error = L::Error>, L: fat::Realloc] core::alloc::Allocator(unstable 1.50) for Segregate<THRESHOLD, S, L> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(all(test, feature = "alloc"))] mod backing_global {
+    use super::*;
+    use crate::allocator::alloc::Global;
+    use crate::allocator::simple::FixedPoolLinearProbe;
+
+    type Pool = FixedPoolLinearProbe<8, 16, 64>;
+
+    #[test] fn routing() {
+        use crate::boxed::ABox;
+
+        let pool = Pool::new();
+        let alloc = Segregate::<16, _, _>(&pool, Global);
+
+        let small = ABox::try_new_in([1u64; 2], &alloc).unwrap();
+        let large = ABox::try_new_in([2u64; 3], &alloc).unwrap();
+        assert!( pool.owns(core::ptr::NonNull::from(&*small).cast()));
+        assert!(!pool.owns(core::ptr::NonNull::from(&*large).cast()));
+    }
+
+    #[test] fn threshold_above_small_max_size() {
+        use crate::boxed::ABox;
+
+        let pool = Pool::new();
+        let alloc = Segregate::<32, _, _>(&pool, Global);
+
+        let small = ABox::try_new_in([1u64; 2], &alloc).unwrap();
+        let large = ABox::try_new_in([2u64; 3], &alloc).unwrap(); // <= THRESHOLD, but > Pool::MAX_SIZE
+        assert!( pool.owns(core::ptr::NonNull::from(&*small).cast()));
+        assert!(!pool.owns(core::ptr::NonNull::from(&*large).cast()));
+    }
+
+    #[test] fn realloc_across_threshold() {
+        use crate::fat::*;
+
+        let pool = Pool::new();
+        let alloc = Segregate::<16, _, _>(&pool, Global);
+        let l8  = Layout::new::<u64>();
+        let l32 = Layout::new::<[u64; 4]>();
+
+        let a = alloc.alloc_uninit(l8).unwrap();
+        // SAFETY: ✔️ `a` was just allocated with `l8`
+        unsafe { a.cast::<u64>().write(42) };
+        assert!(pool.owns(a));
+
+        // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `l8`
+        let b = unsafe { alloc.realloc_uninit(a, l8, l32) }.unwrap();
+        assert!(!pool.owns(b), "should've moved to the large allocator");
+        // SAFETY: ✔️ the first 8 bytes of `b` were copied from `a`
+        assert_eq!(42, unsafe { b.cast::<u64>().read() });
+
+        // SAFETY: ✔️ `b` belongs to `alloc` and was reallocated with `l32`
+        let c = unsafe { alloc.realloc_uninit(b, l32, l8) }.unwrap();
+        assert!(pool.owns(c), "should've moved back to the small allocator");
+        // SAFETY: ✔️ the first 8 bytes of `c` were copied from `b`
+        assert_eq!(42, unsafe { c.cast::<u64>().read() });
+
+        // SAFETY: ✔️ `c` belongs to `alloc` and was reallocated with `l8`
+        unsafe { alloc.free(c, l8) };
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(Segregate::<16, _, _>(&Pool::new(), Global)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(Segregate::<16, _, _>(&Pool::new(), Global)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(Segregate::<16, _, _>(&Pool::new(), Global)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(Segregate::<16, _, _>(&Pool::new(), Global)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(Segregate::<16, _, _>(&Pool::new(), Global)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(Segregate::<16, _, _>(&Pool::new(), Global)) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use super::*;
+    use crate::allocator::c::Malloc;
+    use crate::allocator::simple::FixedPoolLinearProbe;
+
+    type Pool = FixedPoolLinearProbe<8, 16, 64>;
+
+    #[test] fn fat_alignment()          { fat::test::alignment(Segregate::<16, _, _>(&Pool::new(), Malloc)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(Segregate::<16, _, _>(&Pool::new(), Malloc)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(Segregate::<16, _, _>(&Pool::new(), Malloc)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(Segregate::<16, _, _>(&Pool::new(), Malloc)) }
+}
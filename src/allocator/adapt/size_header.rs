@@ -0,0 +1,171 @@
+use crate::*;
+use crate::error::ExcessiveSliceRequestedError;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::mem::size_of;
+use core::ptr::NonNull;
+
+
+
+/// Adapt a [`fat`] allocator to a [`thin`] interface, storing each allocation's size in a hidden header.
+///
+/// The requested size is kept in a prefix immediately before the returned pointer, which allows:
+/// *   [`thin::SizeOf`] / [`thin::SizeOfDebug`] to be implemented reliably, even for allocators with no size query of their own.
+/// *   [`thin::Free`] to be implemented on top of allocators that require a size to free (e.g. [`std::allocator<T>::deallocate`](https://en.cppreference.com/w/cpp/memory/allocator/deallocate).)
+///
+/// Allocations are aligned to the lesser of <code>A::[MAX_ALIGN](Meta::MAX_ALIGN)</code> and 16 bytes, to keep the header small.
+/// The header is also why zero sized allocations are always supported.
+///
+/// ### Example
+/// ```rust
+/// # #[cfg(cpp98)] {
+/// # use ialloc::allocator::{adapt::SizeHeader, cpp::StdAllocator};
+/// # use ialloc::thin::*;
+/// # use core::ffi::c_char;
+/// let alloc = SizeHeader(StdAllocator::<c_char>::new());
+/// let ptr = alloc.alloc_uninit(42).unwrap();
+/// assert_eq!(42, unsafe { alloc.size_of(ptr) });
+/// unsafe { alloc.free(ptr) };
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)] #[repr(transparent)] pub struct SizeHeader<A>(pub A);
+
+impl<A: Meta> SizeHeader<A> {
+    const ALIGN : Alignment = if A::MAX_ALIGN.as_usize() < ALIGN_16.as_usize() { A::MAX_ALIGN } else { ALIGN_16 };
+    const HEADER : usize    = if Self::ALIGN.as_usize() < size_of::<usize>() { size_of::<usize>() } else { Self::ALIGN.as_usize() };
+
+    /// The [`Layout`] of the allocation of `A` backing a `size` byte allocation of `self`.
+    fn layout(size: usize) -> Result<Layout, A::Error> {
+        let requested = ExcessiveSliceRequestedError { requested: size };
+        let total = size.checked_add(Self::HEADER).ok_or(requested)?;
+        Layout::from_size_align(total, Self::ALIGN.as_usize()).map_err(|_| requested.into())
+    }
+
+    /// Write `size` into the header of `raw`, returning the pointer past the header.
+    ///
+    /// ### Safety
+    /// *   `raw` must be an allocation of <code>[layout](Self::layout)\(size\)</code>.
+    unsafe fn write_header(raw: AllocNN, size: usize) -> AllocNN {
+        // SAFETY: ✔️ `raw` is valid for `HEADER + size` bytes per the fn's documented safety preconditions
+        unsafe {
+            raw.as_ptr().cast::<usize>().write_unaligned(size);
+            NonNull::new_unchecked(raw.as_ptr().add(Self::HEADER))
+        }
+    }
+
+    /// Recover the allocation of `A` backing `ptr`, and the size stored in its header.
+    ///
+    /// ### Safety
+    /// *   `ptr` must have been returned by [`write_header`](Self::write_header), and not yet freed.
+    unsafe fn read_header(ptr: AllocNN) -> (AllocNN, usize) {
+        // SAFETY: ✔️ the header immediately precedes `ptr` within the same allocation
+        unsafe {
+            let raw = ptr.as_ptr().sub(Self::HEADER);
+            (NonNull::new_unchecked(raw), raw.cast::<usize>().read_unaligned())
+        }
+    }
+}
+
+
+
+// meta::*
+
+impl<A: Meta> Meta for SizeHeader<A> {
+    type Error                  = A::Error;
+    const MAX_ALIGN : Alignment = Self::ALIGN;
+    const MAX_SIZE  : usize     = A::MAX_SIZE.saturating_sub(Self::HEADER);
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl<A: Meta> ZstSupported for SizeHeader<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: Stateless> Stateless for SizeHeader<A> {}
+
+
+
+// thin::*
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ allocations are offset `HEADER` bytes into an allocation from `A` aligned to `ALIGN`, which `HEADER` is a multiple of
+//
+unsafe impl<A: fat::Alloc> thin::Alloc for SizeHeader<A> {
+    fn alloc_uninit(&self, size: usize) -> Result<AllocNN, Self::Error> {
+        let raw = self.0.alloc_uninit(Self::layout(size)?)?;
+        // SAFETY: ✔️ `raw` was just allocated with `layout(size)`
+        Ok(unsafe { Self::write_header(raw, size) })
+    }
+
+    fn alloc_zeroed(&self, size: usize) -> Result<AllocNN0, Self::Error> {
+        let raw = self.0.alloc_zeroed(Self::layout(size)?)?;
+        // SAFETY: ✔️ `raw` was just allocated with `layout(size)` - only the header is overwritten, the rest remains zeroed
+        Ok(unsafe { Self::write_header(raw.cast(), size) }.cast())
+    }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ frees the allocation of `A` using the same layout it was allocated with
+//
+unsafe impl<A: fat::Free> thin::Free for SizeHeader<A> {
+    unsafe fn free(&self, ptr: AllocNN) {
+        // SAFETY: ✔️ `ptr` belongs to `self` per `thin::Free::free`'s documented safety preconditions
+        let (raw, size) = unsafe { Self::read_header(ptr) };
+        let layout = Self::layout(size).expect("bug: undefined behavior: corrupt size header");
+        // SAFETY: ✔️ `raw` was allocated by `self.0` with `layout`
+        unsafe { self.0.free(raw, layout) }
+    }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ reallocates the allocation of `A` from the layout it was allocated with, then updates the header
+//
+unsafe impl<A: fat::Realloc> thin::Realloc for SizeHeader<A> {
+    const CAN_REALLOC_ZEROED : bool = true;
+
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        // SAFETY: ✔️ `ptr` belongs to `self` per `thin::Realloc::realloc_uninit`'s documented safety preconditions
+        let (raw, old_size) = unsafe { Self::read_header(ptr) };
+        let old_layout = Self::layout(old_size).expect("bug: undefined behavior: corrupt size header");
+        // SAFETY: ✔️ `raw` was allocated by `self.0` with `old_layout`
+        let raw = unsafe { self.0.realloc_uninit(raw, old_layout, Self::layout(new_size)?) }?;
+        // SAFETY: ✔️ `raw` was just reallocated with `layout(new_size)`
+        Ok(unsafe { Self::write_header(raw, new_size) })
+    }
+
+    unsafe fn realloc_zeroed(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        // SAFETY: ✔️ `ptr` belongs to `self` per `thin::Realloc::realloc_zeroed`'s documented safety preconditions
+        let (raw, old_size) = unsafe { Self::read_header(ptr) };
+        let old_layout = Self::layout(old_size).expect("bug: undefined behavior: corrupt size header");
+        // SAFETY: ✔️ `raw` was allocated by `self.0` with `old_layout`, which exactly covers the header and `old_size` bytes - so everything past `old_size` is zeroed
+        let raw = unsafe { self.0.realloc_zeroed(raw, old_layout, Self::layout(new_size)?) }?;
+        // SAFETY: ✔️ `raw` was just reallocated with `layout(new_size)`
+        Ok(unsafe { Self::write_header(raw, new_size) })
+    }
+}
+
+// SAFETY: ✔️ the header always holds the exact size requested
+unsafe impl<A: Meta> thin::SizeOf for SizeHeader<A> {}
+
+// SAFETY: ✔️ the header always holds the exact size requested
+unsafe impl<A: Meta> thin::SizeOfDebug for SizeHeader<A> {
+    unsafe fn size_of_debug(&self, ptr: AllocNN) -> Option<usize> {
+        // SAFETY: ✔️ `ptr` belongs to `self` per `thin::SizeOfDebug::size_of_debug`'s documented safety preconditions
+        Some(unsafe { Self::read_header(ptr) }.1)
+    }
+}
+
+
+
+// fat::*
+
+#[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, SizeHeader};
+
+    impls! {
+        // SAFETY: ✔️ all {thin, fat}::* impls intercompatible with each other where implemented
+        unsafe impl[A: fat::Alloc  ] ialloc::fat::Alloc   for SizeHeader<A> => ialloc::thin::Alloc;
+        unsafe impl[A: fat::Free   ] ialloc::fat::Free    for SizeHeader<A> => ialloc::thin::Free;
+        unsafe impl[A: fat::Realloc] ialloc::fat::Realloc for SizeHeader<A> => ialloc::thin::Realloc;
+    }
+}
@@ -0,0 +1,350 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::any::TypeId;
+use core::cell::RefCell;
+
+
+
+/// Cache small allocations of `A` in per-thread free lists, to reduce contention on `A`.
+///
+/// *   Allocations of up to 256 bytes (and no more than 16 byte alignment) are rounded up to one of 16 size classes.
+/// *   Freed blocks are pushed onto the current thread's free list for their size class, without touching `A`.
+/// *   When a free list grows past 64 blocks, half of it is returned to `A` in one batch.
+/// *   When a thread exits, (or calls [`ThreadCache::flush_thread`],) its free lists are returned to `A`.
+///
+/// `A` must be [`Stateless`]: the free lists are shared by every `ThreadCache<A>` on a thread, and a block is simply pushed onto the free list of whichever thread frees it.
+/// Freeing from a different thread than the one that allocated is thus fine - the block will be reused by (and eventually returned to `A` from) the freeing thread.
+/// This also means `ThreadCache<A>` is itself [`Stateless`], so e.g. [`ABox::from_raw`](crate::boxed::ABox::from_raw) keeps working.
+///
+/// ### Example
+/// ```rust
+/// # use ialloc::allocator::{adapt::ThreadCache, alloc::Global};
+/// # use ialloc::boxed::ABox;
+/// let a = ABox::new_in(1u64, ThreadCache(Global));
+/// drop(a);                                            // cached by this thread...
+/// let b = ABox::new_in(2u64, ThreadCache(Global));    // ...and reused here
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)] #[repr(transparent)] pub struct ThreadCache<A>(pub A);
+
+const CLASS_GRANULARITY : usize = 16;
+const CLASSES           : usize = 16;
+const MAX_CACHED        : usize = 64;
+const MAX_ALLOCATORS    : usize = 8;
+
+impl<A: fat::Free + Stateless + 'static> ThreadCache<A> {
+    /// Return all blocks cached by the current thread to `A`.
+    pub fn flush_thread() {
+        let Ok(Some(blocks)) = CACHES.try_with(|caches| caches.try_borrow_mut().ok().and_then(|mut c| c.slot(TypeId::of::<A>()).map(Slot::take_all))) else { return };
+        for (class, head) in blocks.into_iter().enumerate() {
+            // SAFETY: ✔️ `head` is a list of `class` blocks of `A`, just removed from the cache
+            unsafe { free_list::<A>(head, class) };
+        }
+    }
+
+    /// Pop a cached block of `class` belonging to `A`, if any.
+    fn pop(class: usize) -> Option<AllocNN> {
+        CACHES.try_with(|caches| {
+            let mut caches = caches.try_borrow_mut().ok()?;
+            let list = &mut caches.slot(TypeId::of::<A>())?.lists[class];
+            let block = list.head?;
+            // SAFETY: ✔️ `block` is a cached block, whose first bytes hold the next `Link`
+            list.head = unsafe { block.cast::<Link>().as_ptr().read() };
+            list.len -= 1;
+            Some(block)
+        }).ok().flatten()
+    }
+
+    /// Push `block` onto the free list for `class`, returning it if the cache is unavailable.
+    ///
+    /// ### Safety
+    /// *   `block` must be an allocation of `A` with <code>[class_layout](Self::class_layout)\(class\)</code>, no longer in use.
+    unsafe fn push(block: AllocNN, class: usize) -> Result<(), AllocNN> {
+        let overflow = CACHES.try_with(|caches| {
+            let mut caches = caches.try_borrow_mut().ok()?;
+            let slot = caches.slot_or_insert(TypeId::of::<A>(), free_class::<A>)?;
+            let list = &mut slot.lists[class];
+            // SAFETY: ✔️ `block` is valid for at least `CLASS_GRANULARITY` bytes, suitably aligned for a `Link`
+            unsafe { block.cast::<Link>().as_ptr().write(list.head) };
+            list.head = Some(block);
+            list.len += 1;
+            Some(if list.len > MAX_CACHED { list.split_off(MAX_CACHED / 2) } else { None })
+        }).ok().flatten().ok_or(block)?;
+
+        // SAFETY: ✔️ `overflow` is a list of `class` blocks of `A`, just removed from the cache
+        if let Some(overflow) = overflow { unsafe { free_list::<A>(Some(overflow), class) } }
+        Ok(())
+    }
+}
+
+impl<A: Meta> ThreadCache<A> {
+    const CLASS_ALIGN : usize = if A::MAX_ALIGN.as_usize() < CLASS_GRANULARITY { A::MAX_ALIGN.as_usize() } else { CLASS_GRANULARITY };
+
+    /// The size class `layout` would be cached in, if any.
+    fn class_of(layout: Layout) -> Option<usize> {
+        if layout.size() == 0 || layout.align() > Self::CLASS_ALIGN || Self::CLASS_ALIGN < core::mem::align_of::<Link>() { return None }
+        let class = (layout.size() - 1) / CLASS_GRANULARITY;
+        if class < CLASSES { Some(class) } else { None }
+    }
+
+    /// The layout blocks of `class` are allocated from `A` with.
+    fn class_layout(class: usize) -> Layout {
+        // SAFETY: ✔️ `CLASS_ALIGN` is a power of two no larger than 16, and `(class + 1) * 16` is small
+        unsafe { Layout::from_size_align_unchecked((class + 1) * CLASS_GRANULARITY, Self::CLASS_ALIGN) }
+    }
+}
+
+
+
+// thread local state
+
+/// An intrusive singly linked list node, stored in the first bytes of a cached block.
+type Link = Option<AllocNN>;
+
+#[derive(Clone, Copy)] struct List {
+    head:   Link,
+    len:    usize,
+}
+
+impl List {
+    const EMPTY : Self = Self { head: None, len: 0 };
+
+    /// Keep the first `keep` blocks, returning the rest.
+    fn split_off(&mut self, keep: usize) -> Link {
+        let mut tail = self.head?;
+        for _ in 1 .. keep {
+            // SAFETY: ✔️ `tail` is a cached block, whose first bytes hold the next `Link`
+            tail = unsafe { tail.cast::<Link>().as_ptr().read() }?;
+        }
+        // SAFETY: ✔️ `tail` is a cached block, whose first bytes hold the next `Link`
+        let rest = unsafe { tail.cast::<Link>().as_ptr().replace(None) };
+        self.len = keep;
+        rest
+    }
+}
+
+struct Slot {
+    type_id:    TypeId,
+    free:       unsafe fn(AllocNN, usize),
+    lists:      [List; CLASSES],
+}
+
+impl Slot {
+    fn take_all(&mut self) -> [Link; CLASSES] { core::mem::replace(&mut self.lists, [List::EMPTY; CLASSES]).map(|list| list.head) }
+}
+
+struct Caches([Option<Slot>; MAX_ALLOCATORS]);
+
+impl Caches {
+    const fn new() -> Self { Self([const { None }; MAX_ALLOCATORS]) }
+
+    fn slot(&mut self, type_id: TypeId) -> Option<&mut Slot> { self.0.iter_mut().flatten().find(|slot| slot.type_id == type_id) }
+
+    fn slot_or_insert(&mut self, type_id: TypeId, free: unsafe fn(AllocNN, usize)) -> Option<&mut Slot> {
+        let index = self.0.iter().position(|slot| slot.as_ref().is_some_and(|slot| slot.type_id == type_id))
+            .or_else(|| self.0.iter().position(Option::is_none))?;
+        Some(self.0[index].get_or_insert(Slot { type_id, free, lists: [List::EMPTY; CLASSES] }))
+    }
+}
+
+impl Drop for Caches {
+    fn drop(&mut self) {
+        for slot in self.0.iter_mut().flatten() {
+            let free = slot.free;
+            for (class, head) in slot.take_all().into_iter().enumerate() {
+                let mut next = head;
+                while let Some(block) = next {
+                    // SAFETY: ✔️ `block` is a cached block, whose first bytes hold the next `Link`
+                    next = unsafe { block.cast::<Link>().as_ptr().read() };
+                    // SAFETY: ✔️ `block` is a `class` block of the allocator `free` was instantiated for
+                    unsafe { free(block, class) };
+                }
+            }
+        }
+    }
+}
+
+std::thread_local! {
+    static CACHES : RefCell<Caches> = const { RefCell::new(Caches::new()) };
+}
+
+/// ### Safety
+/// *   `block` must be an allocation of `A` with <code>[ThreadCache::class_layout]\(class\)</code>, no longer in use.
+unsafe fn free_class<A: fat::Free + Stateless>(block: AllocNN, class: usize) {
+    // SAFETY: ✔️ `A` is `Stateless`, so any instance can free `block`
+    unsafe { A::default().free(block, ThreadCache::<A>::class_layout(class)) }
+}
+
+/// ### Safety
+/// *   `head` must be a list of allocations of `A` with <code>[ThreadCache::class_layout]\(class\)</code>, no longer in use or cached.
+unsafe fn free_list<A: fat::Free + Stateless>(head: Link, class: usize) {
+    let mut next = head;
+    while let Some(block) = next {
+        // SAFETY: ✔️ `block` is a formerly cached block, whose first bytes hold the next `Link`
+        next = unsafe { block.cast::<Link>().as_ptr().read() };
+        // SAFETY: ✔️ `block` is a `class` block of `A` per the fn's documented safety preconditions
+        unsafe { free_class::<A>(block, class) };
+    }
+}
+
+
+
+// meta::*
+
+impl<A: Meta> Meta for ThreadCache<A> {
+    type Error                  = A::Error;
+    const MAX_ALIGN : Alignment = A::MAX_ALIGN;
+    const MAX_SIZE  : usize     = A::MAX_SIZE;
+    const ZST_SUPPORTED : bool  = A::ZST_SUPPORTED;
+}
+
+impl<A: ZstSupported> ZstSupported for ThreadCache<A> {}
+
+// SAFETY: ✔️ ZSTs are never cached, and passed straight through to `A`
+unsafe impl<A: ZstInfalliable> ZstInfalliable for ThreadCache<A> {}
+
+// SAFETY: ✔️ the cache is shared by every instance on a thread, and blocks are only ever returned to `A`, which is itself `Stateless`
+unsafe impl<A: Stateless> Stateless for ThreadCache<A> {}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ cacheable layouts come from `A` (possibly via the cache) with a class layout that's at least as large and aligned, others come straight from `A`
+//
+unsafe impl<A: fat::Alloc + fat::Free + Stateless + 'static> fat::Alloc for ThreadCache<A> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> {
+        let Some(class) = Self::class_of(layout) else { return self.0.alloc_uninit(layout) };
+        if let Some(block) = Self::pop(class) { return Ok(block) }
+        self.0.alloc_uninit(Self::class_layout(class))
+    }
+
+    fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> {
+        let Some(class) = Self::class_of(layout) else { return self.0.alloc_zeroed(layout) };
+        if let Some(block) = Self::pop(class) {
+            // SAFETY: ✔️ `block` is valid for `class_layout(class)`, which is at least `layout.size()` bytes
+            unsafe { util::slice::from_raw_bytes_layout_mut(block, layout) }.fill(core::mem::MaybeUninit::new(0));
+            return Ok(block.cast());
+        }
+        self.0.alloc_zeroed(Self::class_layout(class))
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ cacheable layouts are cached (or returned to `A` with their class layout), others are freed straight to `A`
+//
+unsafe impl<A: fat::Free + Stateless + 'static> fat::Free for ThreadCache<A> {
+    unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        let Some(class) = Self::class_of(layout) else {
+            // SAFETY: ✔️ `ptr` was allocated by `self.0` with `layout` per `fat::Free::free`'s documented safety preconditions
+            return unsafe { self.0.free(ptr, layout) };
+        };
+        // SAFETY: ✔️ `ptr` was allocated by `A` with `class_layout(class)`, and is no longer in use per `fat::Free::free`'s documented safety preconditions
+        if let Err(ptr) = unsafe { Self::push(ptr, class) } {
+            // SAFETY: ✔️ the cache is unavailable (thread exiting, reentrant, or full of other allocator types), so free directly
+            unsafe { self.0.free(ptr, Self::class_layout(class)) }
+        }
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ reallocations within the same size class are no-ops, reallocations between uncacheable layouts are forwarded to `A`, others are moved
+//
+unsafe impl<A: fat::Realloc + Stateless + 'static> fat::Realloc for ThreadCache<A> {
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        if old_layout == new_layout { return Ok(ptr) }
+        match (Self::class_of(old_layout), Self::class_of(new_layout)) {
+            (Some(old), Some(new)) if old == new => Ok(ptr),
+            // SAFETY: ✔️ `ptr` was allocated by `self.0` with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+            (None, None) => unsafe { self.0.realloc_uninit(ptr, old_layout, new_layout) },
+            // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+            _ => unsafe { fat::realloc_by_copy(self, self, ptr, old_layout, new_layout) },
+        }
+    }
+
+    unsafe fn realloc_zeroed(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        if Self::class_of(old_layout).is_none() && Self::class_of(new_layout).is_none() {
+            // SAFETY: ✔️ `ptr` was allocated by `self.0` with `old_layout` per `fat::Realloc::realloc_zeroed`'s documented safety preconditions
+            return unsafe { self.0.realloc_zeroed(ptr, old_layout, new_layout) };
+        }
+        // SAFETY: ✔️ realloc_uninit has same prereqs as realloc_zeroed
+        let alloc = unsafe { fat::Realloc::realloc_uninit(self, ptr, old_layout, new_layout) }?;
+        if old_layout.size() < new_layout.size() {
+            // SAFETY: ✔️ `alloc` was just (re)allocated using `new_layout`
+            let all = unsafe { util::slice::from_raw_bytes_layout_mut(alloc, new_layout) };
+            all[old_layout.size()..].fill(core::mem::MaybeUninit::new(0u8));
+        }
+        Ok(alloc)
+    }
+}
+
+#[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, ThreadCache};
+    use crate::meta::Stateless;
+
+    impls! {
+        unsafe impl[A: fat::Realloc + Stateless + 'static] core::alloc::GlobalAlloc for ThreadCache<A> => ialloc::fat::Realloc;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl[A: fat::Realloc + Stateless + 'static] core::alloc::Allocator(unstable 1.50) for ThreadCache<A> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(test)] mod backing_global {
+    use super::*;
+    use crate::allocator::alloc::Global;
+
+    #[test] fn reuse() {
+        use crate::fat::*;
+        let alloc = ThreadCache(Global);
+        let layout = Layout::new::<[u64; 3]>();
+        let a = alloc.alloc_uninit(layout).unwrap();
+        // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `layout`
+        unsafe { alloc.free(a, layout) };
+        let b = alloc.alloc_uninit(Layout::new::<[u32; 7]>()).unwrap(); // same size class
+        assert_eq!(a, b, "block should've been reused from the cache");
+        // SAFETY: ✔️ `b` belongs to `alloc` and was allocated with a layout of the same size class
+        unsafe { ThreadCache(Global).free(b, Layout::new::<[u32; 7]>()) };
+        ThreadCache::<Global>::flush_thread();
+    }
+
+    #[test] fn cross_thread_free() {
+        use crate::boxed::ABox;
+        let boxes = (0 .. 1000u64).map(|i| ABox::try_new_in(i, ThreadCache(Global)).unwrap()).collect::<std::vec::Vec<_>>();
+        std::thread::spawn(move || {
+            for (i, b) in boxes.iter().enumerate() { assert_eq!(i as u64, **b) }
+            drop(boxes); // cached by, then returned to `Global` by, this thread
+        }).join().unwrap();
+    }
+
+    #[test] fn from_raw() {
+        use crate::boxed::ABox;
+        let raw = ABox::into_raw(ABox::try_new_in(42u32, ThreadCache(Global)).unwrap());
+        // SAFETY: ✔️ `raw` came from `ABox::into_raw` with a `Stateless` allocator
+        let b = unsafe { ABox::<u32, ThreadCache<Global>>::from_raw(raw) };
+        assert_eq!(42, *b);
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(ThreadCache(Global)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(ThreadCache(Global)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(ThreadCache(Global)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(ThreadCache(Global)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(ThreadCache(Global)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(ThreadCache(Global)) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use super::*;
+    use crate::allocator::c::Malloc;
+
+    #[test] fn fat_alignment()          { fat::test::alignment(ThreadCache(Malloc)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(ThreadCache(Malloc)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(ThreadCache(Malloc)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(ThreadCache(Malloc)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(ThreadCache(Malloc)) }
+}
@@ -0,0 +1,246 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+
+
+/// Wipe allocations with volatile zeroing writes before they're freed, for e.g. key material.
+///
+/// *   [`thin::Free`] wipes <code>[thin::SizeOf]::[size_of](thin::SizeOf::size_of)</code> bytes, and thus requires `A` to implement [`thin::SizeOf`].
+/// *   [`fat::Free`] wipes [`Layout::size`] bytes.
+/// *   [`thin::Realloc`] and [`fat::Realloc`] always allocate a new block, copy, and wipe + free the old block - as `A`'s own realloc could leave an unwiped copy behind when moving.
+///
+/// Note that this only covers memory handed back to `A`:
+/// copies made by your own code, registers, swap, etc. are out of scope.
+///
+/// ### Example
+/// ```rust
+/// # use ialloc::allocator::{adapt::ZeroOnFree, alloc::Global};
+/// # use ialloc::boxed::ABox;
+/// let key = ABox::new_in([0x42u8; 32], ZeroOnFree(Global));
+/// drop(key); // wiped before being freed
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)] #[repr(transparent)] pub struct ZeroOnFree<A>(pub A);
+
+impl<A> ZeroOnFree<A> {
+    /// Zero `len` bytes at `ptr` in a way the compiler won't elide as a dead store.
+    ///
+    /// ### Safety
+    /// *   `ptr` must be valid for writes of `len` bytes.
+    unsafe fn wipe(ptr: AllocNN, len: usize) {
+        for i in 0 .. len {
+            // SAFETY: ✔️ `i < len`, and `ptr` is valid for `len` bytes per the fn's documented safety preconditions
+            unsafe { ptr.as_ptr().add(i).write_volatile(MaybeUninit::new(0u8)) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+
+
+// meta::*
+
+impl<A: Meta> Meta for ZeroOnFree<A> {
+    type Error                  = A::Error;
+    const MAX_ALIGN : Alignment = A::MAX_ALIGN;
+    const MAX_SIZE  : usize     = A::MAX_SIZE;
+    const ZST_SUPPORTED : bool  = A::ZST_SUPPORTED;
+}
+
+impl<A: ZstSupported> ZstSupported for ZeroOnFree<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: ZstInfalliable> ZstInfalliable for ZeroOnFree<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: Stateless> Stateless for ZeroOnFree<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: Owns> Owns for ZeroOnFree<A> {
+    fn owns(&self, ptr: AllocNN) -> bool { self.0.owns(ptr) }
+}
+
+
+
+// thin::*
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::Alloc> thin::Alloc for ZeroOnFree<A> {
+    fn alloc_uninit(&self, size: usize) -> Result<AllocNN, Self::Error> { self.0.alloc_uninit(size) }
+    fn alloc_zeroed(&self, size: usize) -> Result<AllocNN0, Self::Error> { self.0.alloc_zeroed(size) }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ frees via `A` after wiping
+//
+unsafe impl<A: thin::Free + thin::SizeOf> thin::Free for ZeroOnFree<A> {
+    unsafe fn free(&self, ptr: AllocNN) {
+        // SAFETY: ✔️ `ptr` belongs to `self.0` per `thin::Free::free`'s documented safety preconditions
+        let size = unsafe { self.0.size_of(ptr) };
+        // SAFETY: ✔️ `thin::SizeOf` guarantees `size` bytes are writeable
+        unsafe { Self::wipe(ptr, size) };
+        // SAFETY: ✔️ `ptr` belongs to `self.0` per `thin::Free::free`'s documented safety preconditions
+        unsafe { self.0.free(ptr) }
+    }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ reallocations are fresh allocations from `A`, with the old allocation wiped and freed
+//
+unsafe impl<A: thin::Alloc + thin::Free + thin::SizeOf> thin::Realloc for ZeroOnFree<A> {
+    const CAN_REALLOC_ZEROED : bool = true;
+
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        let alloc = self.0.alloc_uninit(new_size)?;
+        // SAFETY: ✔️ `ptr` belongs to `self.0` per `thin::Realloc::realloc_uninit`'s documented safety preconditions, and `alloc` was just allocated for `new_size` bytes
+        unsafe { self.move_and_free(ptr, alloc, new_size) };
+        Ok(alloc)
+    }
+
+    unsafe fn realloc_zeroed(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        let alloc = self.0.alloc_zeroed(new_size)?.cast();
+        // SAFETY: ✔️ `ptr` belongs to `self.0` per `thin::Realloc::realloc_zeroed`'s documented safety preconditions, and `alloc` was just allocated for `new_size` bytes
+        unsafe { self.move_and_free(ptr, alloc, new_size) };
+        Ok(alloc)
+    }
+}
+
+impl<A: thin::Free + thin::SizeOf> ZeroOnFree<A> {
+    /// Copy up to `new_size` bytes from `ptr` to `alloc`, then wipe and free `ptr`.
+    ///
+    /// ### Safety
+    /// *   `ptr` must be a thin allocation belonging to `self.0`
+    /// *   `alloc` must be valid for `new_size` bytes, and not overlap `ptr`
+    unsafe fn move_and_free(&self, ptr: AllocNN, alloc: AllocNN, new_size: usize) {
+        // SAFETY: ✔️ `ptr` belongs to `self.0` per the fn's documented safety preconditions
+        let old_size = unsafe { self.0.size_of(ptr) };
+        // SAFETY: ✔️ `ptr` is valid for `old_size` bytes, `alloc` is valid for `new_size` bytes, and they don't overlap
+        unsafe { alloc.as_ptr().copy_from_nonoverlapping(ptr.as_ptr(), old_size.min(new_size)) };
+        // SAFETY: ✔️ `ptr` belongs to `self` per the fn's documented safety preconditions
+        unsafe { thin::Free::free(self, ptr) };
+    }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::SizeOf> thin::SizeOf for ZeroOnFree<A> {
+    unsafe fn size_of(&self, ptr: NonNull<MaybeUninit<u8>>) -> usize { unsafe { self.0.size_of(ptr) } }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::SizeOfDebug> thin::SizeOfDebug for ZeroOnFree<A> {
+    unsafe fn size_of_debug(&self, ptr: NonNull<MaybeUninit<u8>>) -> Option<usize> { unsafe { self.0.size_of_debug(ptr) } }
+}
+
+
+
+// fat::*
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: fat::Alloc> fat::Alloc for ZeroOnFree<A> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> { self.0.alloc_uninit(layout) }
+    fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> { self.0.alloc_zeroed(layout) }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ frees via `A` after wiping
+//
+unsafe impl<A: fat::Free> fat::Free for ZeroOnFree<A> {
+    unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        // SAFETY: ✔️ `ptr` is valid for `layout` per `fat::Free::free`'s documented safety preconditions
+        unsafe { Self::wipe(ptr, layout.size()) };
+        // SAFETY: ✔️ `ptr` belongs to `self.0` and was allocated with `layout` per `fat::Free::free`'s documented safety preconditions
+        unsafe { self.0.free(ptr, layout) }
+    }
+}
+
+// SAFETY: ✔️ default Realloc impl is soundly implemented in terms of Alloc+Free - and always wipes the old allocation via our own `fat::Free`
+unsafe impl<A: fat::Alloc + fat::Free> fat::Realloc for ZeroOnFree<A> {}
+
+#[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, ZeroOnFree};
+
+    impls! {
+        unsafe impl[A: fat::Alloc + fat::Free] core::alloc::GlobalAlloc for ZeroOnFree<A> => ialloc::fat::Realloc;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl[A: fat::Alloc + fat::Free] core::alloc::Allocator(unstable 1.50) for ZeroOnFree<A> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(all(test, feature = "alloc"))] mod backing_global {
+    use super::*;
+    use crate::allocator::alloc::Global;
+
+    /// Wraps [`Global`], recording the contents of the last allocation freed.
+    #[derive(Default)] struct Inspect { last_freed: core::cell::RefCell<alloc::vec::Vec<u8>> }
+    impl Meta for Inspect {
+        type Error                  = <Global as Meta>::Error;
+        const MAX_ALIGN : Alignment = <Global as Meta>::MAX_ALIGN;
+        const MAX_SIZE  : usize     = <Global as Meta>::MAX_SIZE;
+        const ZST_SUPPORTED : bool  = <Global as Meta>::ZST_SUPPORTED;
+    }
+    // SAFETY: ✔️ forwards to `Global`
+    unsafe impl fat::Alloc for Inspect {
+        fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> { Global.alloc_uninit(layout) }
+    }
+    // SAFETY: ✔️ forwards to `Global`
+    unsafe impl fat::Free for Inspect {
+        unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+            // SAFETY: ✔️ `ptr` is valid for `layout`, and its contents were initialized by the test (or wiped)
+            let bytes = unsafe { core::slice::from_raw_parts(ptr.as_ptr().cast::<u8>(), layout.size()) };
+            *self.last_freed.borrow_mut() = bytes.into();
+            // SAFETY: ✔️ `ptr` belongs to `Global` and was allocated with `layout`
+            unsafe { Global.free(ptr, layout) }
+        }
+    }
+
+    #[test] fn wipes_on_free() {
+        use crate::boxed::ABox;
+        let inspect = Inspect::default();
+        drop(ABox::try_new_in([0x42u8; 32], ZeroOnFree(&inspect)).unwrap());
+        assert_eq!(&[0u8; 32][..], &inspect.last_freed.borrow()[..]);
+    }
+
+    #[test] fn wipes_on_realloc() {
+        use crate::fat::*;
+        let inspect = Inspect::default();
+        let alloc = ZeroOnFree(&inspect);
+        let l16 = Layout::new::<[u8; 16]>();
+        let l64 = Layout::new::<[u8; 64]>();
+
+        let a = alloc.alloc_uninit(l16).unwrap();
+        // SAFETY: ✔️ `a` was just allocated with `l16`
+        unsafe { a.cast::<[u8; 16]>().write([0x42; 16]) };
+        // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `l16`
+        let b = unsafe { alloc.realloc_uninit(a, l16, l64) }.unwrap();
+        assert_eq!(&[0u8; 16][..], &inspect.last_freed.borrow()[..]);
+        // SAFETY: ✔️ the first 16 bytes of `b` were copied from `a`
+        assert_eq!([0x42; 16], unsafe { b.cast::<[u8; 16]>().read() });
+        // SAFETY: ✔️ `b` belongs to `alloc` and was reallocated with `l64`
+        unsafe { alloc.free(b, l64) };
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(ZeroOnFree(Global)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(ZeroOnFree(Global)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(ZeroOnFree(Global)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(ZeroOnFree(Global)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(ZeroOnFree(Global)) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use super::*;
+    use crate::allocator::{adapt::SizeHeader, c::Malloc};
+
+    #[test] fn thin_alignment()         { thin::test::alignment(ZeroOnFree(SizeHeader(Malloc))) }
+    #[test] fn thin_nullable()          { thin::test::nullable(ZeroOnFree(SizeHeader(Malloc))) }
+    #[test] fn thin_uninit_realloc()    { thin::test::uninit_realloc(ZeroOnFree(SizeHeader(Malloc))) }
+    #[test] fn thin_zeroed()            { thin::test::zeroed_alloc(ZeroOnFree(SizeHeader(Malloc))) }
+    #[test] fn thin_zeroed_realloc()    { thin::test::zeroed_realloc(ZeroOnFree(SizeHeader(Malloc))) }
+}
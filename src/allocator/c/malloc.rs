@@ -277,3 +277,28 @@ unsafe impl thin::SizeOfDebug for Malloc {
     #[test] fn fat_zeroed_realloc()         { fat::test::zeroed_realloc(DangleZst(Malloc)) }
     #[test] fn fat_zst_support()            { fat::test::zst_supported_conservative(DangleZst(Malloc)) }
 }
+
+#[cfg(test)] mod adapt_over_align {
+    use crate::allocator::adapt::OverAlign;
+    use super::{fat, Malloc, MALLOC_ZERO_INITS};
+
+    #[test] fn fat_alignment()              { fat::test::alignment(OverAlign(Malloc)) }
+    #[test] fn fat_edge_case_sizes()        { fat::test::edge_case_sizes(OverAlign(Malloc)) }
+    #[test] fn fat_uninit()                 { if !MALLOC_ZERO_INITS { unsafe { fat::test::uninit_alloc_unsound(OverAlign(Malloc)) } } }
+    #[test] fn fat_uninit_realloc()         { fat::test::uninit_realloc(OverAlign(Malloc)) }
+    #[test] fn fat_zeroed()                 { fat::test::zeroed_alloc(OverAlign(Malloc)) }
+    #[test] fn fat_zeroed_realloc()         { fat::test::zeroed_realloc(OverAlign(Malloc)) }
+    #[test] fn fat_zst_support()            { fat::test::zst_supported_conservative(OverAlign(Malloc)) }
+
+    #[test] fn large_alignments() {
+        use crate::boxed::ABox;
+        #[derive(Clone, Copy)] #[repr(C, align(4096))] struct Page([u8; 4096]);
+        #[derive(Clone, Copy)] #[repr(C, align(65536))] struct Huge(u8);
+
+        let pages = [(); 8].map(|_| ABox::try_new_in(Page([1u8; 4096]), OverAlign(Malloc)).unwrap());
+        for page in pages.iter() { assert_eq!(0, (&**page as *const Page as usize) % 4096) }
+        let huge = ABox::try_new_in(Huge(2), OverAlign(Malloc)).unwrap();
+        assert_eq!(0, (&*huge as *const Huge as usize) % 65536);
+        assert_eq!(2, huge.0);
+    }
+}
@@ -112,3 +112,26 @@ unsafe impl fat::Realloc for NewDelete {}
 #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(NewDelete) }
 #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(NewDelete) }
 #[test] fn fat_zst_support()        { fat::test::zst_supported_conservative(NewDelete) }
+
+#[cfg(test)] mod adapt_over_align {
+    use crate::allocator::adapt::OverAlign;
+    use super::{fat, NewDelete, OPERATOR_NEW_ZERO_INITS};
+
+    #[test] fn fat_alignment()          { fat::test::alignment(OverAlign(NewDelete)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(OverAlign(NewDelete)) }
+    #[test] fn fat_uninit()             { if !OPERATOR_NEW_ZERO_INITS { unsafe { fat::test::uninit_alloc_unsound(OverAlign(NewDelete)) } } }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(OverAlign(NewDelete)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_conservative(OverAlign(NewDelete)) }
+}
+
+#[cfg(test)] mod adapt_size_header {
+    use crate::allocator::adapt::SizeHeader;
+    use super::{thin, NewDelete};
+
+    #[test] fn thin_alignment()         { thin::test::alignment(SizeHeader(NewDelete)) }
+    #[test] fn thin_edge_case_sizes()   { thin::test::edge_case_sizes(SizeHeader(NewDelete)) }
+    #[test] fn thin_nullable()          { thin::test::nullable(SizeHeader(NewDelete)) }
+    #[test] fn thin_size()              { thin::test::size_exact_alloc(SizeHeader(NewDelete)) }
+    #[test] fn thin_zeroed()            { thin::test::zeroed_alloc(SizeHeader(NewDelete)) }
+    #[test] fn thin_zst_support()       { thin::test::zst_supported_accurate(SizeHeader(NewDelete)) }
+}
@@ -111,3 +111,29 @@ unsafe impl fat::Realloc for StdAllocator<c_char> {}
 #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(StdAllocator::<c_char>::new()) }
 #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(StdAllocator::<c_char>::new()) }
 #[test] fn fat_zst_support()        { fat::test::zst_supported_conservative(StdAllocator::<c_char>::new()) }
+
+
+
+#[cfg(test)] mod adapt_size_header {
+    use crate::allocator::adapt::SizeHeader;
+    use super::{thin, fat, StdAllocator, c_char};
+
+    fn alloc() -> SizeHeader<StdAllocator<c_char>> { SizeHeader(StdAllocator::new()) }
+
+    #[test] fn thin_alignment()             { thin::test::alignment(alloc()) }
+    #[test] fn thin_edge_case_sizes()       { thin::test::edge_case_sizes(alloc()) }
+    #[test] fn thin_nullable()              { thin::test::nullable(alloc()) }
+    #[test] fn thin_size()                  { thin::test::size_exact_alloc(alloc()) }
+    #[test] fn thin_uninit()                { if !crate::allocator::cpp::OPERATOR_NEW_ZERO_INITS { unsafe { thin::test::uninit_alloc_unsound(alloc()) } } }
+    #[test] fn thin_uninit_realloc()        { thin::test::uninit_realloc(alloc()) }
+    #[test] fn thin_zeroed()                { thin::test::zeroed_alloc(alloc()) }
+    #[test] fn thin_zeroed_realloc()        { thin::test::zeroed_realloc(alloc()) }
+    #[test] fn thin_zst_support()           { thin::test::zst_supported_accurate(alloc()) }
+
+    #[test] fn fat_alignment()              { fat::test::alignment(alloc()) }
+    #[test] fn fat_edge_case_sizes()        { fat::test::edge_case_sizes(alloc()) }
+    #[test] fn fat_uninit_realloc()         { fat::test::uninit_realloc(alloc()) }
+    #[test] fn fat_zeroed()                 { fat::test::zeroed_alloc(alloc()) }
+    #[test] fn fat_zeroed_realloc()         { fat::test::zeroed_realloc(alloc()) }
+    #[test] fn fat_zst_support()            { fat::test::zst_supported_accurate(alloc()) }
+}
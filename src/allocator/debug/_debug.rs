@@ -1,3 +1,14 @@
-//! [`Null`]
+//! [`FailAfter`], [`FailRandomly`], [`fail_each_allocation`], [`Guard`], [`LeakCheck`], [`Null`], [`Poison`], [`Quarantine`], [`Tracking`], [`ValidateLayouts`]
+//!
+//! [`LeakCheck`], [`Quarantine`], and [`ValidateLayouts`] keep their records in the global allocator (behind a spin lock.)
+//! Used as the `#[global_allocator]`, they'd recurse into themselves while holding that lock - so they don't implement [`GlobalAlloc`](core::alloc::GlobalAlloc).
 
+mod fail;                           pub use fail::*;
+mod guard;                          pub use guard::*;
+#[cfg(feature = "alloc")] mod leak_check;         #[cfg(feature = "alloc")] pub use leak_check::*;
+#[cfg(feature = "alloc")] mod live_records;
 mod null;                           pub use null::*;
+mod poison;                         pub use poison::*;
+#[cfg(feature = "alloc")] mod quarantine;         #[cfg(feature = "alloc")] pub use quarantine::*;
+#[cfg(target_has_atomic = "ptr")] mod tracking;   #[cfg(target_has_atomic = "ptr")] pub use tracking::*;
+#[cfg(feature = "alloc")] mod validate_layouts;   #[cfg(feature = "alloc")] pub use validate_layouts::*;
@@ -0,0 +1,414 @@
+use crate::*;
+use crate::error::ExcessiveSliceRequestedError;
+use crate::meta::*;
+
+#[cfg(feature = "alloc")] use alloc::collections::{BTreeMap, btree_map::Entry};
+
+use core::alloc::Layout;
+use core::cell::Cell;
+#[cfg(feature = "alloc")] use core::cell::RefCell;
+use core::fmt::{self, Debug, Formatter};
+
+
+
+/// Succeed at the first `N` (re)allocations, then fail every (re)allocation after that.
+///
+/// All [`meta`], [`thin`], and [`fat`] traits of `A` are forwarded.
+/// Frees are never failed.  Injected failures are reported as an [`ExcessiveSliceRequestedError`] converted into `A::Error`,
+/// as that's one of the few ways to construct an arbitrary <code>[Meta]::[Error](Meta::Error)</code>.
+///
+/// Also counts (re)allocation attempts and live allocations, which [`fail_each_allocation`] uses to fail every allocation point of a test in turn.
+///
+/// ### Example
+/// ```rust
+/// # use ialloc::allocator::{alloc::Global, debug::FailAfter};
+/// # use ialloc::boxed::ABox;
+/// let alloc = FailAfter::new(Global, 1);
+/// let a = ABox::try_new_in(1u32, &alloc).unwrap();
+/// assert!(ABox::try_new_in(2u32, &alloc).is_err());
+/// ```
+pub struct FailAfter<A> {
+    allocator:  A,
+    remaining:  Cell<usize>,
+    attempts:   Cell<usize>,
+    live:       Cell<usize>,
+    #[cfg(feature = "alloc")] addresses: Option<RefCell<BTreeMap<usize, usize>>>, // address => live allocations (ZSTs may share addresses), if tracked for `fail_each_allocation`
+}
+
+impl<A> FailAfter<A> {
+    /// Wrap `allocator`, allowing `successes` (re)allocations to succeed before failing the rest.
+    pub const fn new(allocator: A, successes: usize) -> Self { Self { allocator, remaining: Cell::new(successes), attempts: Cell::new(0), live: Cell::new(0), #[cfg(feature = "alloc")] addresses: None } }
+
+    /// Like [`FailAfter::new`], but also track live allocations by address (in the global allocator), to catch frees of pointers that were never allocated (or were already freed.)
+    #[cfg(feature = "alloc")] fn new_tracking_addresses(allocator: A, successes: usize) -> Self { Self { addresses: Some(RefCell::new(BTreeMap::new())), ..Self::new(allocator, successes) } }
+
+    /// Unwrap the underlying allocator.
+    pub fn into_inner(self) -> A { self.allocator }
+
+    /// Allow `successes` more (re)allocations to succeed before failing the rest.
+    pub fn set_remaining(&self, successes: usize) { self.remaining.set(successes) }
+
+    /// How many more (re)allocations will succeed before failing.
+    pub fn remaining(&self) -> usize { self.remaining.get() }
+
+    /// How many (re)allocations have been attempted, whether they succeeded or failed.
+    pub fn attempts(&self) -> usize { self.attempts.get() }
+
+    /// How many allocations have been made, but not yet freed.
+    pub fn live(&self) -> usize { self.live.get() }
+
+    fn fail(&self) -> bool {
+        self.attempts.set(self.attempts.get() + 1);
+        let remaining = self.remaining.get();
+        if remaining == 0 { return true }
+        self.remaining.set(remaining - 1);
+        false
+    }
+
+    fn on_alloc(&self, ptr: AllocNN) {
+        self.live.set(self.live.get() + 1);
+        #[cfg(feature = "alloc")] if let Some(addresses) = self.addresses.as_ref() { *addresses.borrow_mut().entry(ptr.as_ptr() as usize).or_default() += 1 }
+        #[cfg(not(feature = "alloc"))] let _ = ptr;
+    }
+
+    #[track_caller] fn on_free(&self, ptr: AllocNN) {
+        #[cfg(feature = "alloc")] if let Some(addresses) = self.addresses.as_ref() {
+            match addresses.borrow_mut().entry(ptr.as_ptr() as usize) {
+                Entry::Vacant(_)                        => panic!("allocator::debug::FailAfter freed {ptr:?}, which isn't a live allocation (double free?)"),
+                Entry::Occupied(e) if *e.get() == 1     => { e.remove(); },
+                Entry::Occupied(mut e)                  => *e.get_mut() -= 1,
+            }
+        }
+        #[cfg(not(feature = "alloc"))] let _ = ptr;
+        self.live.set(self.live.get().checked_sub(1).expect("allocator::debug::FailAfter freed more allocations than it allocated (double free?)"))
+    }
+
+    /// `ptr` is only live again if the reallocation failed
+    #[track_caller] fn on_realloc<E>(&self, ptr: AllocNN, realloc: impl FnOnce() -> Result<AllocNN, E>) -> Result<AllocNN, E> {
+        self.on_free(ptr);
+        let result = realloc();
+        self.on_alloc(*result.as_ref().unwrap_or(&ptr));
+        result
+    }
+}
+
+impl<A> Debug for FailAfter<A> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("FailAfter")
+            .field("remaining", &self.remaining.get())
+            .field("attempts",  &self.attempts.get())
+            .field("live",      &self.live.get())
+            .finish_non_exhaustive()
+    }
+}
+
+
+
+/// Fail (re)allocations at random, with a fixed `probability` - deterministically, for a given `seed`.
+///
+/// All [`meta`], [`thin`], and [`fat`] traits of `A` are forwarded.
+/// Frees are never failed.  Injected failures are reported as an [`ExcessiveSliceRequestedError`] converted into `A::Error`,
+/// as that's one of the few ways to construct an arbitrary <code>[Meta]::[Error](Meta::Error)</code>.
+///
+/// The same seed produces the same sequence of failures on every run and platform (given the same sequence of (re)allocations.)
+///
+/// ### Example
+/// ```rust
+/// # use ialloc::allocator::{alloc::Global, debug::FailRandomly};
+/// # use ialloc::boxed::ABox;
+/// let alloc = FailRandomly::new(Global, 42, 0.5);
+/// let results = (0 .. 100).map(|i| ABox::try_new_in(i, &alloc).is_ok()).collect::<Vec<_>>();
+/// let alloc = FailRandomly::new(Global, 42, 0.5);
+/// assert!((0 .. 100).map(|i| ABox::try_new_in(i, &alloc).is_ok()).eq(results));
+/// ```
+pub struct FailRandomly<A> {
+    allocator:      A,
+    probability:    f64,
+    state:          Cell<u64>,
+}
+
+impl<A> FailRandomly<A> {
+    /// Wrap `allocator`, failing each (re)allocation with a chance of `probability` (`0.0` = never, `1.0` = always.)
+    pub const fn new(allocator: A, seed: u64, probability: f64) -> Self { Self { allocator, probability, state: Cell::new(seed) } }
+
+    /// Unwrap the underlying allocator.
+    pub fn into_inner(self) -> A { self.allocator }
+
+    fn fail(&self) -> bool {
+        // SplitMix64 (<https://prng.di.unimi.it/splitmix64.c>): tiny, and well distributed even for small or zero seeds
+        let state = self.state.get().wrapping_add(0x9E37_79B9_7F4A_7C15);
+        self.state.set(state);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        let unit = (z >> 11) as f64 * (1.0 / (1u64 << 53) as f64); // [0, 1)
+        unit < self.probability
+    }
+
+    fn on_alloc(&self, _ptr: AllocNN) {}
+    fn on_free(&self, _ptr: AllocNN) {}
+    fn on_realloc<E>(&self, _ptr: AllocNN, realloc: impl FnOnce() -> Result<AllocNN, E>) -> Result<AllocNN, E> { realloc() }
+}
+
+impl<A> Debug for FailRandomly<A> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("FailRandomly").field("probability", &self.probability).finish_non_exhaustive()
+    }
+}
+
+
+
+/// Run `test` repeatedly, failing the 1st, 2nd, 3rd, ... (re)allocation in turn, until a run completes without reaching the failure.
+///
+/// After each run, this asserts every allocation `test` made through its [`FailAfter`] was freed.
+/// Live allocations are tracked by address (in the global allocator), so freeing or reallocating a pointer that was never allocated, or was already freed, panics immediately -
+/// letting tests reach every allocation error path of e.g. <code>[ABox](crate::boxed::ABox)::try_new_in</code> or <code>[AVec](crate::vec::AVec)::try_reserve</code>,
+/// and verify those paths don't leak.  Pair with your own drop counters to check values aren't leaked or double dropped either.
+///
+/// Returns the number of (re)allocations made by the final, failure free run.
+///
+/// ### Example
+/// ```rust
+/// # use ialloc::allocator::{alloc::Global, debug::fail_each_allocation};
+/// # use ialloc::boxed::ABox;
+/// let points = fail_each_allocation(Global, |alloc| {
+///     let Ok(a) = ABox::try_new_in(1u32, alloc) else { return };
+///     let Ok(b) = ABox::try_new_in(2u32, alloc) else { return };
+///     assert_eq!(3, *a + *b);
+/// });
+/// assert_eq!(2, points);
+/// ```
+#[cfg(feature = "alloc")] #[track_caller] pub fn fail_each_allocation<A: Clone>(allocator: A, mut test: impl FnMut(&FailAfter<A>)) -> usize {
+    for fail_at in 0 .. {
+        let alloc = FailAfter::new_tracking_addresses(allocator.clone(), fail_at);
+        test(&alloc);
+        assert!(alloc.live() == 0, "allocator::debug::fail_each_allocation: {} allocation(s) leaked when failing (re)allocation #{fail_at}", alloc.live());
+        if alloc.attempts() <= fail_at { return alloc.attempts() }
+    }
+    unreachable!("more than usize::MAX allocations")
+}
+
+
+
+macro_rules! forward_fail {
+    ( $fail:ident ) => {
+        // meta::*
+
+        impl<A: Meta> Meta for $fail<A> {
+            type Error                  = A::Error;
+            const MAX_ALIGN : Alignment = A::MAX_ALIGN;
+            const MAX_SIZE  : usize     = A::MAX_SIZE;
+            const ZST_SUPPORTED : bool  = A::ZST_SUPPORTED;
+        }
+
+        impl<A: ZstSupported> ZstSupported for $fail<A> {}
+
+        // SAFETY: ✔️ per underlying allocator
+        unsafe impl<A: Owns> Owns for $fail<A> {
+            fn owns(&self, ptr: AllocNN) -> bool { self.allocator.owns(ptr) }
+        }
+
+        // ZstInfalliable intentionally not forwarded: ZST allocations can be failed too
+
+
+
+        // thin::*
+
+        // SAFETY: ✔️ implemented against same traits with same prereqs - failing is always an option
+        unsafe impl<A: thin::Alloc> thin::Alloc for $fail<A> {
+            fn alloc_uninit(&self, size: usize) -> Result<AllocNN, Self::Error> {
+                if self.fail() { return Err(ExcessiveSliceRequestedError { requested: size }.into()) }
+                let ptr = self.allocator.alloc_uninit(size)?;
+                self.on_alloc(ptr);
+                Ok(ptr)
+            }
+
+            fn alloc_zeroed(&self, size: usize) -> Result<AllocNN0, Self::Error> {
+                if self.fail() { return Err(ExcessiveSliceRequestedError { requested: size }.into()) }
+                let ptr = self.allocator.alloc_zeroed(size)?;
+                self.on_alloc(ptr.cast());
+                Ok(ptr)
+            }
+        }
+
+        // SAFETY: ✔️ implemented against same traits with same prereqs
+        unsafe impl<A: thin::Free> thin::Free for $fail<A> {
+            #[track_caller] unsafe fn free(&self, ptr: AllocNN) {
+                self.on_free(ptr);
+                // SAFETY: ✔️ same trait, same prereqs
+                unsafe { self.allocator.free(ptr) }
+            }
+        }
+
+        // SAFETY: ✔️ implemented against same traits with same prereqs - failing is always an option
+        unsafe impl<A: thin::Realloc> thin::Realloc for $fail<A> {
+            const CAN_REALLOC_ZEROED : bool = A::CAN_REALLOC_ZEROED;
+
+            unsafe fn realloc_uninit(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+                if self.fail() { return Err(ExcessiveSliceRequestedError { requested: new_size }.into()) }
+                // SAFETY: ✔️ same trait, same prereqs
+                self.on_realloc(ptr, || unsafe { self.allocator.realloc_uninit(ptr, new_size) })
+            }
+
+            unsafe fn realloc_zeroed(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+                if self.fail() { return Err(ExcessiveSliceRequestedError { requested: new_size }.into()) }
+                // SAFETY: ✔️ same trait, same prereqs
+                self.on_realloc(ptr, || unsafe { self.allocator.realloc_zeroed(ptr, new_size) })
+            }
+        }
+
+        #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+        unsafe impl<A: thin::SizeOf> thin::SizeOf for $fail<A> {
+            unsafe fn size_of(&self, ptr: AllocNN) -> usize { unsafe { self.allocator.size_of(ptr) } }
+        }
+
+        #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+        unsafe impl<A: thin::SizeOfDebug> thin::SizeOfDebug for $fail<A> {
+            unsafe fn size_of_debug(&self, ptr: AllocNN) -> Option<usize> { unsafe { self.allocator.size_of_debug(ptr) } }
+        }
+
+
+
+        // fat::*
+
+        // SAFETY: ✔️ implemented against same traits with same prereqs - failing is always an option
+        unsafe impl<A: fat::Alloc> fat::Alloc for $fail<A> {
+            fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> {
+                if self.fail() { return Err(ExcessiveSliceRequestedError { requested: layout.size() }.into()) }
+                let ptr = self.allocator.alloc_uninit(layout)?;
+                self.on_alloc(ptr);
+                Ok(ptr)
+            }
+
+            fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> {
+                if self.fail() { return Err(ExcessiveSliceRequestedError { requested: layout.size() }.into()) }
+                let ptr = self.allocator.alloc_zeroed(layout)?;
+                self.on_alloc(ptr.cast());
+                Ok(ptr)
+            }
+        }
+
+        // SAFETY: ✔️ implemented against same traits with same prereqs
+        unsafe impl<A: fat::Free> fat::Free for $fail<A> {
+            #[track_caller] unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+                self.on_free(ptr);
+                // SAFETY: ✔️ same trait, same prereqs
+                unsafe { self.allocator.free(ptr, layout) }
+            }
+        }
+
+        // SAFETY: ✔️ implemented against same traits with same prereqs - failing is always an option
+        unsafe impl<A: fat::Realloc> fat::Realloc for $fail<A> {
+            unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+                if self.fail() { return Err(ExcessiveSliceRequestedError { requested: new_layout.size() }.into()) }
+                // SAFETY: ✔️ same trait, same prereqs
+                self.on_realloc(ptr, || unsafe { self.allocator.realloc_uninit(ptr, old_layout, new_layout) })
+            }
+
+            unsafe fn realloc_zeroed(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+                if self.fail() { return Err(ExcessiveSliceRequestedError { requested: new_layout.size() }.into()) }
+                // SAFETY: ✔️ same trait, same prereqs
+                self.on_realloc(ptr, || unsafe { self.allocator.realloc_zeroed(ptr, old_layout, new_layout) })
+            }
+        }
+    };
+}
+
+forward_fail!(FailAfter);
+forward_fail!(FailRandomly);
+
+#[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, FailAfter, FailRandomly};
+
+    impls! {
+        unsafe impl[A: fat::Realloc] core::alloc::GlobalAlloc for FailAfter<A>    => ialloc::fat::Realloc;
+        unsafe impl[A: fat::Realloc] core::alloc::GlobalAlloc for FailRandomly<A> => ialloc::fat::Realloc;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl[A: fat::Realloc] core::alloc::Allocator(unstable 1.50) for FailAfter<A>    => ialloc::fat::Realloc;
+        unsafe impl[A: fat::Realloc] core::alloc::Allocator(unstable 1.50) for FailRandomly<A> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(all(test, feature = "alloc"))] mod backing_global {
+    use super::*;
+    use crate::allocator::alloc::Global;
+    use crate::boxed::ABox;
+    use crate::util::drop::Tester;
+    use crate::vec::AVec;
+
+    #[test] fn fail_after() {
+        let alloc = FailAfter::new(Global, 2);
+        let a = ABox::try_new_in(1u32, &alloc).unwrap();
+        let b = ABox::try_new_in(2u32, &alloc).unwrap();
+        assert!(ABox::try_new_in(3u32, &alloc).is_err());
+        assert_eq!((0, 3, 2), (alloc.remaining(), alloc.attempts(), alloc.live()));
+        drop((a, b));
+        assert_eq!(0, alloc.live());
+        alloc.set_remaining(1);
+        assert!(ABox::try_new_in(4u32, &alloc).is_ok());
+    }
+
+    #[test] fn fail_randomly() {
+        let pattern = |seed, probability| { let alloc = FailRandomly::new(Global, seed, probability); (0 .. 1000).map(|i| ABox::try_new_in(i, &alloc).is_ok()).collect::<alloc::vec::Vec<_>>() };
+        assert_eq!(pattern(1, 0.5), pattern(1, 0.5));
+        assert_ne!(pattern(1, 0.5), pattern(2, 0.5));
+        assert!(pattern(3, 0.0).iter().all(|ok| *ok));
+        assert!(pattern(3, 1.0).iter().all(|ok| !*ok));
+        let successes = pattern(4, 0.25).iter().filter(|ok| **ok).count();
+        assert!((650 .. 850).contains(&successes), "{successes} successes of 1000 with a 25% failure rate");
+    }
+
+    #[test] fn fail_each() {
+        let points = fail_each_allocation(Global, |alloc| {
+            let Ok(mut v) = AVec::<Tester, _>::try_with_capacity_in(0, alloc) else { return };
+            for i in 0 .. 10 { if v.try_push(Tester::new(i)).is_err() { return } }
+            let Ok(b) = ABox::try_new_in(Tester::new(42), alloc) else { return };
+            assert_eq!(10, v.len());
+            assert_eq!(42, **b);
+        });
+        assert!(points >= 3, "{points}");
+        assert!(Tester::counts().iter().all(|c| *c == 0), "Tester leaked or double dropped");
+    }
+
+    #[test] #[should_panic = "1 allocation(s) leaked when failing (re)allocation #1"] fn fail_each_leak() {
+        fail_each_allocation(Global, |alloc| {
+            let Ok(a) = ABox::try_new_in(1u32, alloc) else { return };
+            let Ok(_) = ABox::try_new_in(2u32, alloc) else { core::mem::forget(a); return }; // oops
+        });
+    }
+
+    #[test] #[should_panic = "which isn't a live allocation (double free?)"] fn fail_each_double_free() {
+        use crate::fat::*;
+        fail_each_allocation(Global, |alloc| {
+            let layout = Layout::new::<u32>();
+            let Ok(a) = alloc.alloc_uninit(layout) else { return };
+            // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `layout`
+            let Ok(_) = alloc.alloc_uninit(layout) else { return unsafe { alloc.free(a, layout) } }; // oops: leaked...
+            // SAFETY: ❌ intentionally freeing `a` twice to test the bug report
+            unsafe { alloc.free(a, layout); alloc.free(a, layout) }; // ...but a net live count of 0
+        });
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(FailAfter::new(Global, usize::MAX)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(FailAfter::new(Global, usize::MAX)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(FailAfter::new(Global, usize::MAX)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(FailRandomly::new(Global, 0, 0.5)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(FailAfter::new(Global, usize::MAX)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_conservative(FailAfter::new(Global, usize::MAX)) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use super::*;
+    use crate::allocator::c::Malloc;
+
+    #[test] fn thin_alignment()         { thin::test::alignment(FailRandomly::new(Malloc, 0, 0.5)) }
+    #[test] fn thin_nullable()          { thin::test::nullable(FailAfter::new(Malloc, usize::MAX)) }
+    #[test] fn thin_uninit_realloc()    { thin::test::uninit_realloc(FailAfter::new(Malloc, usize::MAX)) }
+    #[test] fn thin_zeroed()            { thin::test::zeroed_alloc(FailRandomly::new(Malloc, 0, 0.5)) }
+    #[test] fn thin_zeroed_realloc()    { thin::test::zeroed_realloc(FailAfter::new(Malloc, usize::MAX)) }
+}
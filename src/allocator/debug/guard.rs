@@ -0,0 +1,266 @@
+use crate::*;
+use crate::error::ExcessiveSliceRequestedError;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::mem::size_of;
+use core::ptr::NonNull;
+
+
+
+/// Surround every allocation with canary bytes, and check they're intact when freed or reallocated.
+///
+/// Each allocation of `A` is laid out as `[size | canary] [data] [canary]`.
+/// Overwriting any canary byte - typically by a buffer overrun or underrun - is reported by [`thin::Free::free`], [`fat::Free::free`], or realloc panicking,
+/// rather than going unnoticed until the heap corruption causes a crash somewhere else much later.
+///
+/// The hidden size header (see [`adapt::SizeHeader`](crate::allocator::adapt::SizeHeader)) lets `Guard` locate the trailing canary bytes from a pointer alone,
+/// so `Guard` provides [`thin`] allocation even when `A` itself is [`fat`]-only - or when `A` is a [`thin`] allocator like [`Malloc`](crate::allocator::c::Malloc), which also implements [`fat`].
+///
+/// Allocations are aligned to the lesser of <code>A::[MAX_ALIGN](Meta::MAX_ALIGN)</code> and 16 bytes.
+///
+/// ### Example
+/// ```rust,should_panic
+/// # use ialloc::allocator::{alloc::Global, debug::Guard};
+/// # use ialloc::thin::*;
+/// let alloc = Guard(Global);
+/// let ptr = alloc.alloc_zeroed(4).unwrap();
+/// unsafe { ptr.as_ptr().add(4).write(0xFF) }; // oops, off by one
+/// unsafe { alloc.free(ptr.cast()) }; // panics: "...the guard bytes after ... were overwritten..."
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)] #[repr(transparent)] pub struct Guard<A>(pub A);
+
+impl<A: Meta> Guard<A> {
+    const ALIGN     : Alignment = if A::MAX_ALIGN.as_usize() < ALIGN_16.as_usize() { A::MAX_ALIGN } else { ALIGN_16 };
+    const CANARY    : u8        = 0xFD;
+    const BACK      : usize     = 16;
+    const FRONT     : usize     = (size_of::<usize>() + 16 + Self::ALIGN.as_usize() - 1) & !(Self::ALIGN.as_usize() - 1); // size + ≥16 canary bytes, rounded up to ALIGN
+
+    /// The [`Layout`] of the allocation of `A` backing a `size` byte allocation of `self`.
+    fn layout(size: usize) -> Result<Layout, A::Error> {
+        let requested = ExcessiveSliceRequestedError { requested: size };
+        let total = size.checked_add(Self::FRONT + Self::BACK).ok_or(requested)?;
+        Layout::from_size_align(total, Self::ALIGN.as_usize()).map_err(|_| requested.into())
+    }
+
+    /// Write `size` and the canary bytes around `raw`, returning the pointer past the front canary.
+    ///
+    /// ### Safety
+    /// *   `raw` must be an allocation of <code>[layout](Self::layout)\(size\)</code>.
+    unsafe fn write_guards(raw: AllocNN, size: usize) -> AllocNN {
+        // SAFETY: ✔️ `raw` is valid for `FRONT + size + BACK` bytes per the fn's documented safety preconditions
+        unsafe {
+            let raw = raw.as_ptr();
+            raw.cast::<usize>().write_unaligned(size);
+            raw.add(size_of::<usize>()).write_bytes(Self::CANARY, Self::FRONT - size_of::<usize>());
+            raw.add(Self::FRONT + size).write_bytes(Self::CANARY, Self::BACK);
+            NonNull::new_unchecked(raw.add(Self::FRONT))
+        }
+    }
+
+    /// Recover the allocation of `A` backing `ptr` and its size, after checking the canary bytes are intact.
+    ///
+    /// ### Safety
+    /// *   `ptr` must have been returned by [`write_guards`](Self::write_guards), and not yet freed.
+    #[track_caller] unsafe fn check_guards(ptr: AllocNN) -> (AllocNN, Layout) {
+        let intact = |start: *const u8, len: usize| {
+            // SAFETY: ✔️ callers below only pass canary bytes within the allocation
+            unsafe { core::slice::from_raw_parts(start, len) }.iter().all(|b| *b == Self::CANARY)
+        };
+
+        // SAFETY: ✔️ the size and front canary immediately precede `ptr` within the same allocation
+        let (raw, size) = unsafe {
+            let raw = ptr.as_ptr().sub(Self::FRONT);
+            (NonNull::new_unchecked(raw), raw.cast::<usize>().read_unaligned())
+        };
+        let layout = Layout::from_size_align(size, Self::ALIGN.as_usize()).ok().filter(|_| Self::layout(size).is_ok());
+
+        // SAFETY: ✔️ the front canary lies between the size and `ptr`
+        if !intact(unsafe { raw.as_ptr().add(size_of::<usize>()) }.cast(), Self::FRONT - size_of::<usize>()) { bug::ub::clobbered_guard_bytes(ptr, layout, "before") }
+        let Some(layout) = layout else { bug::ub::clobbered_guard_bytes(ptr, None, "before") };
+        // SAFETY: ✔️ the back canary immediately follows the `size` bytes of `ptr`
+        if !intact(unsafe { ptr.as_ptr().add(size) }.cast(), Self::BACK) { bug::ub::clobbered_guard_bytes(ptr, Some(layout), "after") }
+
+        (raw, layout)
+    }
+}
+
+
+
+// meta::*
+
+impl<A: Meta> Meta for Guard<A> {
+    type Error                  = A::Error;
+    const MAX_ALIGN : Alignment = Self::ALIGN;
+    const MAX_SIZE  : usize     = A::MAX_SIZE.saturating_sub(Self::FRONT + Self::BACK);
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl<A: Meta> ZstSupported for Guard<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: Stateless> Stateless for Guard<A> {}
+
+
+
+// thin::*
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ allocations are offset `FRONT` bytes into an allocation from `A` aligned to `ALIGN`, which `FRONT` is a multiple of
+//
+unsafe impl<A: fat::Alloc> thin::Alloc for Guard<A> {
+    fn alloc_uninit(&self, size: usize) -> Result<AllocNN, Self::Error> {
+        let raw = self.0.alloc_uninit(Self::layout(size)?)?;
+        // SAFETY: ✔️ `raw` was just allocated with `layout(size)`
+        Ok(unsafe { Self::write_guards(raw, size) })
+    }
+
+    fn alloc_zeroed(&self, size: usize) -> Result<AllocNN0, Self::Error> {
+        let raw = self.0.alloc_zeroed(Self::layout(size)?)?;
+        // SAFETY: ✔️ `raw` was just allocated with `layout(size)` - only the guards are overwritten, the data remains zeroed
+        Ok(unsafe { Self::write_guards(raw.cast(), size) }.cast())
+    }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ frees the allocation of `A` using the same layout it was allocated with
+//
+unsafe impl<A: fat::Free> thin::Free for Guard<A> {
+    #[track_caller] unsafe fn free(&self, ptr: AllocNN) {
+        // SAFETY: ✔️ `ptr` belongs to `self` per `thin::Free::free`'s documented safety preconditions
+        let (raw, layout) = unsafe { Self::check_guards(ptr) };
+        // SAFETY: ✔️ `raw` was allocated by `self.0` with `layout(layout.size())`, which `check_guards` verified is valid
+        unsafe { self.0.free(raw, Self::layout(layout.size()).unwrap_or_else(|_| unreachable!())) }
+    }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ reallocates the allocation of `A` from the layout it was allocated with, then rewrites the guards
+//
+unsafe impl<A: fat::Realloc> thin::Realloc for Guard<A> {
+    const CAN_REALLOC_ZEROED : bool = true;
+
+    #[track_caller] unsafe fn realloc_uninit(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        // SAFETY: ✔️ `ptr` belongs to `self` per `thin::Realloc::realloc_uninit`'s documented safety preconditions
+        let (raw, old) = unsafe { Self::check_guards(ptr) };
+        let old_layout = Self::layout(old.size()).unwrap_or_else(|_| unreachable!());
+        // SAFETY: ✔️ `raw` was allocated by `self.0` with `old_layout`
+        let raw = unsafe { self.0.realloc_uninit(raw, old_layout, Self::layout(new_size)?) }?;
+        // SAFETY: ✔️ `raw` was just reallocated with `layout(new_size)`
+        Ok(unsafe { Self::write_guards(raw, new_size) })
+    }
+
+    #[track_caller] unsafe fn realloc_zeroed(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        // SAFETY: ✔️ `ptr` belongs to `self` per `thin::Realloc::realloc_zeroed`'s documented safety preconditions
+        let (raw, old) = unsafe { Self::check_guards(ptr) };
+        let old_layout = Self::layout(old.size()).unwrap_or_else(|_| unreachable!());
+        // SAFETY: ✔️ `raw` was allocated by `self.0` with `old_layout`, which exactly covers the guards and `old.size()` bytes - so everything past the old back canary is zeroed
+        let raw = unsafe { self.0.realloc_zeroed(raw, old_layout, Self::layout(new_size)?) }?;
+        // SAFETY: ✔️ `raw` was just reallocated with `layout(new_size)`.  The old back canary was copied along with the data, and must be re-zeroed where it now overlaps the data.
+        unsafe {
+            let stale = new_size.saturating_sub(old.size()).min(Self::BACK);
+            raw.as_ptr().add(Self::FRONT + old.size()).write_bytes(0, stale);
+            Ok(Self::write_guards(raw, new_size))
+        }
+    }
+}
+
+// SAFETY: ✔️ the header always holds the exact size requested
+unsafe impl<A: Meta> thin::SizeOf for Guard<A> {}
+
+// SAFETY: ✔️ the header always holds the exact size requested
+unsafe impl<A: Meta> thin::SizeOfDebug for Guard<A> {
+    unsafe fn size_of_debug(&self, ptr: AllocNN) -> Option<usize> {
+        // SAFETY: ✔️ `ptr` belongs to `self` per `thin::SizeOfDebug::size_of_debug`'s documented safety preconditions
+        Some(unsafe { ptr.as_ptr().sub(Self::FRONT).cast::<usize>().read_unaligned() })
+    }
+}
+
+
+
+// fat::*
+
+#[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, Guard};
+
+    impls! {
+        // SAFETY: ✔️ all {thin, fat}::* impls intercompatible with each other where implemented
+        unsafe impl[A: fat::Alloc  ] ialloc::fat::Alloc   for Guard<A> => ialloc::thin::Alloc;
+        unsafe impl[A: fat::Free   ] ialloc::fat::Free    for Guard<A> => ialloc::thin::Free;
+        unsafe impl[A: fat::Realloc] ialloc::fat::Realloc for Guard<A> => ialloc::thin::Realloc;
+
+        unsafe impl[A: fat::Realloc] core::alloc::GlobalAlloc for Guard<A> => ialloc::fat::Realloc;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl[A: fat::Realloc] core::alloc::Allocator(unstable 1.50) for Guard<A> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(all(test, feature = "alloc"))] mod backing_global {
+    use super::*;
+    use crate::allocator::alloc::Global;
+
+    #[test] #[should_panic = "the guard bytes after"] fn overrun() {
+        use crate::thin::*;
+        let alloc = Guard(Global);
+        let ptr = alloc.alloc_zeroed(4).unwrap();
+        // SAFETY: ❌ intentionally writing one byte past the end of `ptr` to clobber the guard bytes - still within the allocation of `Global`
+        unsafe { ptr.as_ptr().cast::<u8>().add(4).write(0xFF) };
+        // SAFETY: ✔️ `ptr` belongs to `alloc`
+        unsafe { alloc.free(ptr.cast()) };
+    }
+
+    #[test] #[should_panic = "the guard bytes before"] fn underrun() {
+        use crate::fat::*;
+        let alloc = Guard(Global);
+        let layout = Layout::new::<u32>();
+        let ptr = alloc.alloc_zeroed(layout).unwrap();
+        // SAFETY: ❌ intentionally writing one byte before the start of `ptr` to clobber the guard bytes - still within the allocation of `Global`
+        unsafe { ptr.as_ptr().cast::<u8>().sub(1).write(0xFF) };
+        // SAFETY: ✔️ `ptr` belongs to `alloc` and was allocated with `layout`
+        unsafe { alloc.free(ptr.cast(), layout) };
+    }
+
+    #[test] #[should_panic = "the guard bytes after"] fn overrun_realloc() {
+        use crate::thin::*;
+        let alloc = Guard(Global);
+        let ptr = alloc.alloc_zeroed(4).unwrap();
+        // SAFETY: ❌ intentionally writing past the end of `ptr` to clobber the guard bytes - still within the allocation of `Global`
+        unsafe { ptr.as_ptr().cast::<u8>().add(8).write(0xFF) };
+        // SAFETY: ✔️ `ptr` belongs to `alloc`
+        let _ = unsafe { alloc.realloc_uninit(ptr.cast(), 8) };
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(Guard(Global)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(Guard(Global)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(Guard(Global)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(Guard(Global)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(Guard(Global)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(Guard(Global)) }
+
+    #[test] fn thin_alignment()         { thin::test::alignment(Guard(Global)) }
+    #[test] fn thin_edge_case_sizes()   { thin::test::edge_case_sizes(Guard(Global)) }
+    #[test] fn thin_nullable()          { thin::test::nullable(Guard(Global)) }
+    #[test] fn thin_size()              { thin::test::size_exact_alloc(Guard(Global)) }
+    #[test] fn thin_uninit_realloc()    { thin::test::uninit_realloc(Guard(Global)) }
+    #[test] fn thin_zeroed()            { thin::test::zeroed_alloc(Guard(Global)) }
+    #[test] fn thin_zeroed_realloc()    { thin::test::zeroed_realloc(Guard(Global)) }
+    #[test] fn thin_zst_support()       { thin::test::zst_supported_accurate(Guard(Global)) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use super::*;
+    use crate::allocator::c::Malloc;
+
+    #[test] fn thin_alignment()         { thin::test::alignment(Guard(Malloc)) }
+    #[test] fn thin_edge_case_sizes()   { thin::test::edge_case_sizes(Guard(Malloc)) }
+    #[test] fn thin_nullable()          { thin::test::nullable(Guard(Malloc)) }
+    #[test] fn thin_size()              { thin::test::size_exact_alloc(Guard(Malloc)) }
+    #[test] fn thin_uninit_realloc()    { thin::test::uninit_realloc(Guard(Malloc)) }
+    #[test] fn thin_zeroed()            { thin::test::zeroed_alloc(Guard(Malloc)) }
+    #[test] fn thin_zeroed_realloc()    { thin::test::zeroed_realloc(Guard(Malloc)) }
+    #[test] fn thin_zst_support()       { thin::test::zst_supported_accurate(Guard(Malloc)) }
+}
@@ -0,0 +1,362 @@
+use crate::*;
+use crate::meta::*;
+use super::live_records::LiveRecords;
+
+use alloc::vec::Vec;
+
+use core::alloc::Layout;
+use core::fmt::{self, Debug, Display, Formatter};
+use core::panic::Location;
+
+#[cfg(feature = "std")] use std::backtrace::{Backtrace, BacktraceStatus};
+#[cfg(feature = "std")] use std::sync::Arc;
+
+
+
+/// Record every live allocation made through an allocator, and report those still outstanding when [checked](Self::check) or dropped.
+///
+/// Each allocation's address, [`Layout`], and [`#[track_caller]`](https://doc.rust-lang.org/reference/attributes/codegen.html#the-track_caller-attribute) [`Location`] are recorded.
+/// With the `std` feature, a [`Backtrace`] is also [captured](Backtrace::capture) (subject to the usual `RUST_BACKTRACE` / `RUST_LIB_BACKTRACE` environment variables.)
+/// [`thin`] allocations don't have a known alignment, and are recorded with an alignment of 1.
+///
+/// Dropping a `LeakCheck` with outstanding allocations panics with a report of every leak.
+/// Freeing or reallocating a pointer that isn't a live allocation also panics, instead of forwarding the undefined behavior to `A`.
+///
+/// ### Example
+/// ```rust
+/// # use ialloc::allocator::{alloc::Global, debug::LeakCheck};
+/// # use ialloc::boxed::ABox;
+/// let alloc = LeakCheck::new(Global);
+/// let a = ABox::new_in(1u32, &alloc);
+/// let b = ABox::new_in(2u32, &alloc);
+/// drop(a);
+/// let report = alloc.check();
+/// assert_eq!(report.leaks.len(), 1);
+/// assert_eq!(report.leaks[0].layout.size(), 4);
+/// drop(b);
+/// alloc.assert_no_leaks();
+/// ```
+pub struct LeakCheck<A> {
+    allocator:  A,
+    live:       LiveRecords<Record>,
+}
+
+#[derive(Clone)] struct Record {
+    layout:     Layout,
+    location:   &'static Location<'static>,
+    #[cfg(feature = "std")] backtrace: Arc<Backtrace>,
+}
+
+/// A single outstanding allocation, as reported by [`LeakCheck::check`].
+#[derive(Clone)] pub struct Leak {
+    /// The address of the allocation.  For identification only - this isn't a usable pointer.
+    pub address:    usize,
+    /// The [`Layout`] the allocation was (re)allocated with.
+    pub layout:     Layout,
+    /// Where the allocation was (re)allocated from.
+    pub location:   &'static Location<'static>,
+    /// The backtrace of the (re)allocation, if [captured](Backtrace::capture).
+    #[cfg(feature = "std")] pub backtrace: Arc<Backtrace>,
+}
+
+/// Every outstanding allocation of a [`LeakCheck`], in order of allocation.
+#[derive(Clone, Debug, Default)] pub struct LeakReport {
+    /// The outstanding allocations.
+    pub leaks: Vec<Leak>,
+}
+
+impl LeakReport {
+    /// Returns `true` if there were no outstanding allocations.
+    pub fn is_empty(&self) -> bool { self.leaks.is_empty() }
+}
+
+impl Debug for Leak {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Leak")
+            .field("address",   &format_args!("{:#x}", self.address))
+            .field("layout",    &self.layout)
+            .field("location",  &self.location)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for Leak {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{:#x}: {} bytes (align {}) allocated at {}", self.address, self.layout.size(), self.layout.align(), self.location)?;
+        #[cfg(feature = "std")] if self.backtrace.status() == BacktraceStatus::Captured { write!(fmt, "\n{}", self.backtrace)?; }
+        Ok(())
+    }
+}
+
+impl Display for LeakReport {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{} leaked allocation(s)", self.leaks.len())?;
+        for leak in self.leaks.iter() { write!(fmt, "\n{leak}")?; }
+        Ok(())
+    }
+}
+
+impl<A> LeakCheck<A> {
+    /// Start checking allocations made through `allocator` for leaks.
+    pub const fn new(allocator: A) -> Self { Self { allocator, live: LiveRecords::new() } }
+
+    /// Report every outstanding allocation.
+    pub fn check(&self) -> LeakReport {
+        LeakReport { leaks: self.live.snapshot().into_iter().map(|(address, record)| Leak {
+            address,
+            layout:     record.layout,
+            location:   record.location,
+            #[cfg(feature = "std")] backtrace: record.backtrace,
+        }).collect() }
+    }
+
+    /// Panic with a report of every outstanding allocation, if there are any.
+    #[track_caller] pub fn assert_no_leaks(&self) {
+        let report = self.check();
+        assert!(report.is_empty(), "allocator::debug::LeakCheck has outstanding allocations: {report}");
+    }
+
+    #[track_caller] fn insert(&self, ptr: AllocNN, layout: Layout) {
+        let location = Location::caller();
+        #[cfg(feature = "std")] let backtrace = Arc::new(Backtrace::capture());
+        self.live.insert(ptr, Record { layout, location, #[cfg(feature = "std")] backtrace });
+    }
+
+    fn thin_layout(size: usize) -> Layout {
+        Layout::from_size_align(size, 1).unwrap() // a successful allocation can't exceed `isize::MAX` bytes
+    }
+}
+
+impl<A> Drop for LeakCheck<A> {
+    fn drop(&mut self) {
+        #[cfg(feature = "std")] if std::thread::panicking() { return } // avoid a double panic abort hiding the original panic
+        self.assert_no_leaks();
+    }
+}
+
+impl<A: Default> Default for LeakCheck<A> { fn default() -> Self { Self::new(A::default()) } }
+impl<A> From<A> for LeakCheck<A> { fn from(allocator: A) -> Self { Self::new(allocator) } }
+
+impl<A> Debug for LeakCheck<A> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("LeakCheck").field("live", &self.live.len()).finish_non_exhaustive()
+    }
+}
+
+
+
+// meta::*
+
+impl<A: Meta> Meta for LeakCheck<A> {
+    type Error                  = A::Error;
+    const MAX_ALIGN : Alignment = A::MAX_ALIGN;
+    const MAX_SIZE  : usize     = A::MAX_SIZE;
+    const ZST_SUPPORTED : bool  = A::ZST_SUPPORTED;
+}
+
+impl<A: ZstSupported> ZstSupported for LeakCheck<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: ZstInfalliable> ZstInfalliable for LeakCheck<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: Owns> Owns for LeakCheck<A> {
+    fn owns(&self, ptr: AllocNN) -> bool { self.allocator.owns(ptr) }
+}
+
+
+
+// thin::*
+
+// SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::Alloc> thin::Alloc for LeakCheck<A> {
+    #[track_caller] fn alloc_uninit(&self, size: usize) -> Result<AllocNN, Self::Error> {
+        let ptr = self.allocator.alloc_uninit(size)?;
+        self.insert(ptr, Self::thin_layout(size));
+        Ok(ptr)
+    }
+
+    #[track_caller] fn alloc_zeroed(&self, size: usize) -> Result<AllocNN0, Self::Error> {
+        let ptr = self.allocator.alloc_zeroed(size)?;
+        self.insert(ptr.cast(), Self::thin_layout(size));
+        Ok(ptr)
+    }
+}
+
+// SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::Free> thin::Free for LeakCheck<A> {
+    #[track_caller] unsafe fn free(&self, ptr: AllocNN) {
+        if self.live.remove_ptr(ptr).is_none() { bug::ub::invalid_ptr_for_allocator(ptr) }
+        // SAFETY: ✔️ same prereqs, and `ptr` was a live allocation
+        unsafe { self.allocator.free(ptr) }
+    }
+}
+
+// SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::Realloc> thin::Realloc for LeakCheck<A> {
+    const CAN_REALLOC_ZEROED : bool = A::CAN_REALLOC_ZEROED;
+
+    #[track_caller] unsafe fn realloc_uninit(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        if !self.live.contains(ptr) { bug::ub::invalid_ptr_for_allocator(ptr) }
+        // SAFETY: ✔️ same prereqs, and `ptr` is a live allocation
+        let new = unsafe { self.allocator.realloc_uninit(ptr, new_size) }?;
+        self.live.remove_ptr(ptr);
+        self.insert(new, Self::thin_layout(new_size));
+        Ok(new)
+    }
+
+    #[track_caller] unsafe fn realloc_zeroed(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        if !self.live.contains(ptr) { bug::ub::invalid_ptr_for_allocator(ptr) }
+        // SAFETY: ✔️ same prereqs, and `ptr` is a live allocation
+        let new = unsafe { self.allocator.realloc_zeroed(ptr, new_size) }?;
+        self.live.remove_ptr(ptr);
+        self.insert(new, Self::thin_layout(new_size));
+        Ok(new)
+    }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::SizeOf> thin::SizeOf for LeakCheck<A> {
+    unsafe fn size_of(&self, ptr: AllocNN) -> usize { unsafe { self.allocator.size_of(ptr) } }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::SizeOfDebug> thin::SizeOfDebug for LeakCheck<A> {
+    unsafe fn size_of_debug(&self, ptr: AllocNN) -> Option<usize> { unsafe { self.allocator.size_of_debug(ptr) } }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: fat::Alloc> fat::Alloc for LeakCheck<A> {
+    #[track_caller] fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> {
+        let ptr = self.allocator.alloc_uninit(layout)?;
+        self.insert(ptr, layout);
+        Ok(ptr)
+    }
+
+    #[track_caller] fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> {
+        let ptr = self.allocator.alloc_zeroed(layout)?;
+        self.insert(ptr.cast(), layout);
+        Ok(ptr)
+    }
+}
+
+// SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: fat::Free> fat::Free for LeakCheck<A> {
+    #[track_caller] unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        if self.live.remove_ptr(ptr).is_none() { bug::ub::invalid_ptr_for_allocator(ptr) }
+        // SAFETY: ✔️ same prereqs, and `ptr` was a live allocation
+        unsafe { self.allocator.free(ptr, layout) }
+    }
+}
+
+// SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: fat::Realloc> fat::Realloc for LeakCheck<A> {
+    #[track_caller] unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        if !self.live.contains(ptr) { bug::ub::invalid_ptr_for_allocator(ptr) }
+        // SAFETY: ✔️ same prereqs, and `ptr` is a live allocation
+        let new = unsafe { self.allocator.realloc_uninit(ptr, old_layout, new_layout) }?;
+        self.live.remove_ptr(ptr);
+        self.insert(new, new_layout);
+        Ok(new)
+    }
+
+    #[track_caller] unsafe fn realloc_zeroed(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        if !self.live.contains(ptr) { bug::ub::invalid_ptr_for_allocator(ptr) }
+        // SAFETY: ✔️ same prereqs, and `ptr` is a live allocation
+        let new = unsafe { self.allocator.realloc_zeroed(ptr, old_layout, new_layout) }?;
+        self.live.remove_ptr(ptr);
+        self.insert(new, new_layout);
+        Ok(new)
+    }
+}
+
+#[cfg(allocator_api = "1.50")] #[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, LeakCheck};
+
+    impls! {
+        unsafe impl[A: fat::Realloc] core::alloc::Allocator(unstable 1.50) for LeakCheck<A> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(test)] mod backing_global {
+    use super::*;
+    use crate::allocator::alloc::Global;
+
+    #[test] fn report() {
+        use crate::fat::*;
+        let alloc = LeakCheck::new(Global);
+        let l16 = Layout::new::<[u16; 8]>();
+        let l64 = Layout::new::<[u32; 16]>();
+
+        let line = line!(); let a = alloc.alloc_uninit(l16).unwrap();
+        let b = alloc.alloc_zeroed(l64).unwrap();
+        let report = alloc.check();
+        assert_eq!(2, report.leaks.len());
+        assert_eq!((a.as_ptr() as usize, l16, line), (report.leaks[0].address, report.leaks[0].layout, report.leaks[0].location.line()));
+        assert_eq!((b.as_ptr() as usize, l64), (report.leaks[1].address, report.leaks[1].layout));
+        assert_eq!(file!(), report.leaks[0].location.file());
+
+        // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `l16`
+        let line = line!(); let a = unsafe { alloc.realloc_uninit(a, l16, l64) }.unwrap();
+        // SAFETY: ✔️ `b` belongs to `alloc` and was allocated with `l64`
+        unsafe { alloc.free(b.cast(), l64) };
+        let report = alloc.check();
+        assert_eq!(1, report.leaks.len());
+        assert_eq!((a.as_ptr() as usize, l64, line), (report.leaks[0].address, report.leaks[0].layout, report.leaks[0].location.line()));
+
+        // SAFETY: ✔️ `a` belongs to `alloc` and was reallocated with `l64`
+        unsafe { alloc.free(a, l64) };
+        alloc.assert_no_leaks();
+    }
+
+    #[test] fn zsts_share_addresses() {
+        use crate::fat::*;
+        let alloc = LeakCheck::new(Global);
+        let zst = Layout::new::<()>();
+        let a = alloc.alloc_uninit(zst).unwrap();
+        let b = alloc.alloc_uninit(zst).unwrap();
+        assert_eq!(2, alloc.check().leaks.len());
+        // SAFETY: ✔️ `a` and `b` belong to `alloc` and were allocated with `zst`
+        unsafe { alloc.free(a, zst); alloc.free(b, zst) };
+    }
+
+    #[test] #[should_panic = "has outstanding allocations: 1 leaked allocation(s)"] fn leak_on_drop() {
+        let alloc = LeakCheck::new(Global);
+        let _ = crate::boxed::ABox::leak(crate::boxed::ABox::try_new_in(42u8, &alloc).unwrap());
+    }
+
+    #[test] #[should_panic = "doesn't belong to this allocator"] fn double_free() {
+        use crate::fat::*;
+        let alloc = LeakCheck::new(Global);
+        let layout = Layout::new::<u32>();
+        let a = alloc.alloc_uninit(layout).unwrap();
+        // SAFETY: ❌ intentionally freeing `a` twice to test the bug report
+        unsafe { alloc.free(a, layout); alloc.free(a, layout) };
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(LeakCheck::new(Global)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(LeakCheck::new(Global)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(LeakCheck::new(Global)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(LeakCheck::new(Global)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(LeakCheck::new(Global)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(LeakCheck::new(Global)) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use super::*;
+    use crate::allocator::c::Malloc;
+
+    #[test] fn thin_alignment()         { thin::test::alignment(LeakCheck::new(Malloc)) }
+    #[test] fn thin_edge_case_sizes()   { thin::test::edge_case_sizes(LeakCheck::new(Malloc)) }
+    #[test] fn thin_nullable()          { thin::test::nullable(LeakCheck::new(Malloc)) }
+    #[test] fn thin_uninit_realloc()    { thin::test::uninit_realloc(LeakCheck::new(Malloc)) }
+    #[test] fn thin_zeroed()            { thin::test::zeroed_alloc(LeakCheck::new(Malloc)) }
+    #[test] fn thin_zeroed_realloc()    { thin::test::zeroed_realloc(LeakCheck::new(Malloc)) }
+    #[test] fn thin_zst_support()       { thin::test::zst_supported_conservative(LeakCheck::new(Malloc)) }
+}
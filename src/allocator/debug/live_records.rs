@@ -0,0 +1,91 @@
+use crate::*;
+use crate::allocator::adapt::Locked;
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec::Vec;
+
+use core::cell::RefCell;
+
+
+
+/// A record `R` of every live allocation of a debug allocator, plus the addresses of the most recent frees.
+pub(super) struct LiveRecords<R> {
+    state: Locked<RefCell<State<R>>>,
+}
+
+struct State<R> {
+    next:       u64,
+    records:    BTreeMap<Key, R>,
+    freed:      VecDeque<usize>, // the last `FREED_HISTORY` addresses freed, to tell double frees from foreign pointers
+}
+
+/// (address, allocation order) - ZSTs may share addresses
+pub(super) type Key = (usize, u64);
+
+impl<R> LiveRecords<R> {
+    pub const FREED_HISTORY : usize = 1024;
+
+    pub const fn new() -> Self { Self { state: Locked::new(RefCell::new(State { next: 0, records: BTreeMap::new(), freed: VecDeque::new() })) } }
+
+    fn with<T>(&self, f: impl FnOnce(&mut State<R>) -> T) -> T {
+        // SAFETY: ✔️ `state` doesn't escape - `f` is always one of the closures below
+        unsafe { self.state.with(|state| f(&mut state.borrow_mut())) }
+    }
+
+    fn range(address: usize) -> core::ops::RangeInclusive<Key> { (address, 0) ..= (address, u64::MAX) }
+
+    /// The number of live allocations.
+    pub fn len(&self) -> usize { self.with(|state| state.records.len()) }
+
+    pub fn insert(&self, ptr: AllocNN, record: R) {
+        self.with(|state| {
+            let order = state.next;
+            state.next += 1;
+            state.records.insert((ptr.as_ptr() as usize, order), record);
+        })
+    }
+
+    /// Returns `true` if `ptr` is a live allocation.
+    pub fn contains(&self, ptr: AllocNN) -> bool { self.with(|state| state.records.range(Self::range(ptr.as_ptr() as usize)).next().is_some()) }
+
+    /// Find the oldest live allocation at `ptr` whose record `matches`.
+    /// On failure, returns the oldest record at `ptr` that didn't match, if there were any.
+    pub fn find(&self, ptr: AllocNN, mut matches: impl FnMut(&R) -> bool) -> Result<Key, Option<R>> where R: Clone {
+        self.with(|state| {
+            let mut mismatched = None;
+            for (key, record) in state.records.range(Self::range(ptr.as_ptr() as usize)) {
+                if matches(record) { return Ok(*key) }
+                mismatched.get_or_insert_with(|| record.clone());
+            }
+            Err(mismatched)
+        })
+    }
+
+    /// Remove the record of a live allocation, as found by [`find`](Self::find).
+    pub fn remove(&self, key: Key) -> Option<R> {
+        self.with(|state| {
+            let record = state.records.remove(&key)?;
+            if state.freed.len() == Self::FREED_HISTORY { state.freed.pop_front(); }
+            state.freed.push_back(key.0);
+            Some(record)
+        })
+    }
+
+    /// Remove the record of the oldest live allocation at `ptr`, or return `None` if `ptr` wasn't a live allocation.
+    pub fn remove_ptr(&self, ptr: AllocNN) -> Option<R> {
+        let key = self.with(|state| state.records.range(Self::range(ptr.as_ptr() as usize)).next().map(|(key, _)| *key))?;
+        self.remove(key)
+    }
+
+    /// Returns `true` if `ptr` was one of the last [`FREED_HISTORY`](Self::FREED_HISTORY) addresses freed (it may since have been reallocated.)
+    pub fn was_freed(&self, ptr: AllocNN) -> bool { self.with(|state| state.freed.contains(&(ptr.as_ptr() as usize))) }
+
+    /// Every live allocation's address and record, in order of allocation.
+    pub fn snapshot(&self) -> Vec<(usize, R)> where R: Clone {
+        let mut records = self.with(|state| state.records.iter().map(|(&(address, order), record)| (order, address, record.clone())).collect::<Vec<_>>());
+        records.sort_by_key(|(order, _, _)| *order);
+        records.into_iter().map(|(_, address, record)| (address, record)).collect()
+    }
+
+    #[cfg(test)] pub fn freed_len(&self) -> usize { self.with(|state| state.freed.len()) }
+}
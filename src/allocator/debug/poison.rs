@@ -0,0 +1,269 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+
+
+/// Fill uninitialized memory with `UNINIT`, and freed memory with `FREED`, like MSVC's debug heap does.
+///
+/// *   [`thin::Alloc::alloc_uninit`] and [`fat::Alloc::alloc_uninit`] fill the entire allocation with `UNINIT`.
+/// *   [`thin::Free`] fills <code>[thin::SizeOfDebug]::[size_of_debug](thin::SizeOfDebug::size_of_debug)</code> bytes with `FREED`, when `A` can report that.
+/// *   [`fat::Free`] fills [`Layout::size`] bytes with `FREED`.
+/// *   [`thin::Realloc`] and [`fat::Realloc`] always move to a new allocation, then free the old one through `self` - so growth is filled with `UNINIT`, and the old block with `FREED`.
+///     Thin reallocations are forwarded to `A` unmodified if it can't report the old size via [`thin::SizeOfDebug`].
+///
+/// Zeroing allocations are forwarded to `A` unmodified.
+///
+/// Reading `0xCDCDCDCD...` out of something thus reliably indicates a read of uninitialized memory, and `0xDDDDDDDD...` a use-after-free -
+/// instead of whatever the underlying allocator happened to leave in that memory.
+/// Pair with e.g. [`adapt::SizeHeader`](crate::allocator::adapt::SizeHeader) to poison thin allocations of allocators which can't report their sizes.
+///
+/// ### Example
+/// ```rust
+/// # use ialloc::allocator::{alloc::Global, debug::Poison};
+/// # use ialloc::fat::*;
+/// # use core::alloc::Layout;
+/// let alloc = Poison::<_>(Global); // or e.g. Poison::<_, 0xAA, 0xBB>(Global) for custom patterns
+/// let layout = Layout::new::<u32>();
+/// let ptr = alloc.alloc_uninit(layout).unwrap();
+/// assert_eq!(0xCDCDCDCD, unsafe { ptr.cast::<u32>().read() });
+/// unsafe { alloc.free(ptr, layout) };
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)] #[repr(transparent)] pub struct Poison<A, const UNINIT: u8 = 0xCD, const FREED: u8 = 0xDD>(pub A);
+
+impl<A, const UNINIT: u8, const FREED: u8> Poison<A, UNINIT, FREED> {
+    /// Fill `len` bytes at `ptr` with `UNINIT`.
+    ///
+    /// ### Safety
+    /// *   `ptr` must be valid for writes of `len` bytes.
+    unsafe fn fill_uninit(ptr: AllocNN, len: usize) {
+        // SAFETY: ✔️ per the fn's documented safety preconditions
+        unsafe { ptr.as_ptr().write_bytes(UNINIT, len) }
+    }
+
+    /// Fill `len` bytes at `ptr` with `FREED`, in a way the compiler won't elide as a dead store.
+    ///
+    /// ### Safety
+    /// *   `ptr` must be valid for writes of `len` bytes.
+    unsafe fn fill_freed(ptr: AllocNN, len: usize) {
+        for i in 0 .. len {
+            // SAFETY: ✔️ `i < len`, and `ptr` is valid for `len` bytes per the fn's documented safety preconditions
+            unsafe { ptr.as_ptr().add(i).write_volatile(MaybeUninit::new(FREED)) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+
+
+// meta::*
+
+impl<A: Meta, const UNINIT: u8, const FREED: u8> Meta for Poison<A, UNINIT, FREED> {
+    type Error                  = A::Error;
+    const MAX_ALIGN : Alignment = A::MAX_ALIGN;
+    const MAX_SIZE  : usize     = A::MAX_SIZE;
+    const ZST_SUPPORTED : bool  = A::ZST_SUPPORTED;
+}
+
+impl<A: ZstSupported, const UNINIT: u8, const FREED: u8> ZstSupported for Poison<A, UNINIT, FREED> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: ZstInfalliable, const UNINIT: u8, const FREED: u8> ZstInfalliable for Poison<A, UNINIT, FREED> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: Stateless, const UNINIT: u8, const FREED: u8> Stateless for Poison<A, UNINIT, FREED> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: Owns, const UNINIT: u8, const FREED: u8> Owns for Poison<A, UNINIT, FREED> {
+    fn owns(&self, ptr: AllocNN) -> bool { self.0.owns(ptr) }
+}
+
+
+
+// thin::*
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ allocates via `A`, filling only the bytes just allocated
+//
+unsafe impl<A: thin::Alloc, const UNINIT: u8, const FREED: u8> thin::Alloc for Poison<A, UNINIT, FREED> {
+    fn alloc_uninit(&self, size: usize) -> Result<AllocNN, Self::Error> {
+        let ptr = self.0.alloc_uninit(size)?;
+        // SAFETY: ✔️ `ptr` was just allocated for `size` bytes
+        unsafe { Self::fill_uninit(ptr, size) };
+        Ok(ptr)
+    }
+
+    fn alloc_zeroed(&self, size: usize) -> Result<AllocNN0, Self::Error> { self.0.alloc_zeroed(size) }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ frees via `A` after filling
+//
+unsafe impl<A: thin::Free + thin::SizeOfDebug, const UNINIT: u8, const FREED: u8> thin::Free for Poison<A, UNINIT, FREED> {
+    unsafe fn free(&self, ptr: AllocNN) {
+        // SAFETY: ✔️ `ptr` belongs to `self.0` per `thin::Free::free`'s documented safety preconditions
+        if let Some(size) = unsafe { self.0.size_of_debug(ptr) } {
+            // SAFETY: ✔️ `thin::SizeOfDebug` guarantees `size` bytes are writeable
+            unsafe { Self::fill_freed(ptr, size) };
+        }
+        // SAFETY: ✔️ `ptr` belongs to `self.0` per `thin::Free::free`'s documented safety preconditions
+        unsafe { self.0.free(ptr) }
+    }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ reallocations are fresh allocations from `A`, with the old allocation filled and freed - or forwarded to `A` if the old size is unknown
+//
+unsafe impl<A: thin::Realloc + thin::SizeOfDebug, const UNINIT: u8, const FREED: u8> thin::Realloc for Poison<A, UNINIT, FREED> {
+    const CAN_REALLOC_ZEROED : bool = A::CAN_REALLOC_ZEROED;
+
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        // SAFETY: ✔️ `ptr` belongs to `self.0` per `thin::Realloc::realloc_uninit`'s documented safety preconditions
+        let Some(old_size) = (unsafe { self.0.size_of_debug(ptr) }) else { return unsafe { self.0.realloc_uninit(ptr, new_size) } };
+        let alloc = thin::Alloc::alloc_uninit(self, new_size)?;
+        // SAFETY: ✔️ `ptr` belongs to `self.0` and is valid for `old_size` bytes, and `alloc` was just allocated for `new_size` bytes
+        unsafe { self.move_and_free(ptr, old_size, alloc, new_size) };
+        Ok(alloc)
+    }
+
+    unsafe fn realloc_zeroed(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        // SAFETY: ✔️ `ptr` belongs to `self.0` per `thin::Realloc::realloc_zeroed`'s documented safety preconditions
+        let Some(old_size) = (unsafe { self.0.size_of_debug(ptr) }) else { return unsafe { self.0.realloc_zeroed(ptr, new_size) } };
+        let alloc = self.0.alloc_zeroed(new_size)?.cast();
+        // SAFETY: ✔️ `ptr` belongs to `self.0` and is valid for `old_size` bytes, and `alloc` was just allocated for `new_size` bytes
+        unsafe { self.move_and_free(ptr, old_size, alloc, new_size) };
+        Ok(alloc)
+    }
+}
+
+impl<A: thin::Free + thin::SizeOfDebug, const UNINIT: u8, const FREED: u8> Poison<A, UNINIT, FREED> {
+    /// Copy up to `new_size` bytes from `ptr` to `alloc`, then fill and free `ptr`.
+    ///
+    /// ### Safety
+    /// *   `ptr` must be a thin allocation belonging to `self.0`, valid for `old_size` bytes
+    /// *   `alloc` must be valid for `new_size` bytes, and not overlap `ptr`
+    unsafe fn move_and_free(&self, ptr: AllocNN, old_size: usize, alloc: AllocNN, new_size: usize) {
+        // SAFETY: ✔️ `ptr` and `alloc` are valid for at least `old_size.min(new_size)` bytes, and don't overlap, per the fn's documented safety preconditions
+        unsafe { core::ptr::copy_nonoverlapping(ptr.as_ptr(), alloc.as_ptr(), old_size.min(new_size)) };
+        // SAFETY: ✔️ `ptr` belongs to `self.0` per the fn's documented safety preconditions
+        unsafe { thin::Free::free(self, ptr) };
+    }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::SizeOf, const UNINIT: u8, const FREED: u8> thin::SizeOf for Poison<A, UNINIT, FREED> {
+    unsafe fn size_of(&self, ptr: AllocNN) -> usize { unsafe { self.0.size_of(ptr) } }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::SizeOfDebug, const UNINIT: u8, const FREED: u8> thin::SizeOfDebug for Poison<A, UNINIT, FREED> {
+    unsafe fn size_of_debug(&self, ptr: AllocNN) -> Option<usize> { unsafe { self.0.size_of_debug(ptr) } }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocates via `A`, filling only the bytes just allocated
+//
+unsafe impl<A: fat::Alloc, const UNINIT: u8, const FREED: u8> fat::Alloc for Poison<A, UNINIT, FREED> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> {
+        let ptr = self.0.alloc_uninit(layout)?;
+        // SAFETY: ✔️ `ptr` was just allocated for `layout`
+        unsafe { Self::fill_uninit(ptr, layout.size()) };
+        Ok(ptr)
+    }
+
+    fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> { self.0.alloc_zeroed(layout) }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ frees via `A` after filling
+//
+unsafe impl<A: fat::Free, const UNINIT: u8, const FREED: u8> fat::Free for Poison<A, UNINIT, FREED> {
+    unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        // SAFETY: ✔️ `ptr` is valid for `layout` per `fat::Free::free`'s documented safety preconditions
+        unsafe { Self::fill_freed(ptr, layout.size()) };
+        // SAFETY: ✔️ `ptr` belongs to `self.0` and was allocated with `layout` per `fat::Free::free`'s documented safety preconditions
+        unsafe { self.0.free(ptr, layout) }
+    }
+}
+
+// SAFETY: ✔️ default Realloc impl is soundly implemented in terms of Alloc+Free - and always fills the old allocation via our own `fat::Free`
+unsafe impl<A: fat::Alloc + fat::Free, const UNINIT: u8, const FREED: u8> fat::Realloc for Poison<A, UNINIT, FREED> {}
+
+#[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, Poison};
+
+    impls! {
+        unsafe impl[A: fat::Alloc + fat::Free, const UNINIT: u8, const FREED: u8] core::alloc::GlobalAlloc for Poison<A, UNINIT, FREED> => ialloc::fat::Realloc;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl[A: fat::Alloc + fat::Free, const UNINIT: u8, const FREED: u8] core::alloc::Allocator(unstable 1.50) for Poison<A, UNINIT, FREED> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(test)] mod backing_bump {
+    use super::*;
+    use crate::allocator::simple::Bump;
+
+    #[test] fn fills() {
+        use crate::fat::*;
+        let mut buffer = [MaybeUninit::new(0u8); 64];
+        {
+            let bump = Bump::new(&mut buffer[..]);
+            let alloc = Poison::<_, 0xAA, 0xBB>(&bump);
+            let l8  = Layout::new::<[u8;  8]>();
+            let l16 = Layout::new::<[u8; 16]>();
+
+            let ptr = alloc.alloc_uninit(l8).unwrap();
+            // SAFETY: ✔️ `ptr` was allocated with `l8`, and filled by `alloc_uninit`
+            assert_eq!([0xAA; 8], unsafe { ptr.cast::<[u8; 8]>().read() });
+            // SAFETY: ✔️ `ptr` was allocated with `l8`
+            unsafe { ptr.cast::<[u8; 8]>().write([0x42; 8]) };
+
+            // SAFETY: ✔️ `ptr` belongs to `alloc` and was allocated with `l8`
+            let ptr = unsafe { alloc.realloc_uninit(ptr, l8, l16) }.unwrap();
+            // SAFETY: ✔️ `ptr` was reallocated with `l16`, copying 8 bytes and filling the growth (and freeing the old 8 bytes through `alloc`)
+            assert_eq!([0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA], unsafe { ptr.cast::<[u8; 16]>().read() });
+
+            // SAFETY: ✔️ `ptr` belongs to `alloc` and was reallocated with `l16`
+            unsafe { alloc.free(ptr, l16) };
+        }
+        // SAFETY: ✔️ `buffer` started initialized, and `Poison` only ever writes initialized bytes into it
+        let buffer = buffer.map(|b| unsafe { b.assume_init() });
+        assert!(buffer[..24].iter().all(|b| *b == 0xBB), "{buffer:02x?}"); // the original 8 bytes, then the 16 bytes they were moved to
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))] mod backing_global {
+    use super::*;
+    use crate::allocator::alloc::Global;
+
+    #[test] fn fat_alignment()          { fat::test::alignment(Poison::<_>(Global)) }
+    #[test] fn fat_uninit()             { unsafe { fat::test::uninit_alloc_unsound(Poison::<_>(Global)) } }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(Poison::<_>(Global)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(Poison::<_>(Global)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(Poison::<_>(Global)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(Poison::<_>(Global)) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use super::*;
+    use crate::allocator::{adapt::SizeHeader, c::Malloc};
+
+    #[test] fn thin_alignment()         { thin::test::alignment(Poison::<_>(SizeHeader(Malloc))) }
+    #[test] fn thin_nullable()          { thin::test::nullable(Poison::<_>(SizeHeader(Malloc))) }
+    #[test] fn thin_uninit()            { unsafe { thin::test::uninit_alloc_unsound(Poison::<_>(SizeHeader(Malloc))) } }
+    #[test] fn thin_uninit_realloc()    { thin::test::uninit_realloc(Poison::<_>(SizeHeader(Malloc))) }
+    #[test] fn thin_zeroed()            { thin::test::zeroed_alloc(Poison::<_>(SizeHeader(Malloc))) }
+    #[test] fn thin_zeroed_realloc()    { thin::test::zeroed_realloc(Poison::<_>(SizeHeader(Malloc))) }
+    #[test] fn thin_zst_support()       { thin::test::zst_supported_accurate(Poison::<_>(SizeHeader(Malloc))) }
+}
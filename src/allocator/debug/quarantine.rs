@@ -0,0 +1,269 @@
+use crate::*;
+use crate::allocator::adapt::Locked;
+use crate::meta::*;
+
+use alloc::collections::{BTreeSet, VecDeque};
+
+use core::alloc::Layout;
+use core::cell::RefCell;
+use core::fmt::{self, Debug, Display, Formatter};
+use core::panic::Location;
+
+#[cfg(feature = "std")] use std::backtrace::{Backtrace, BacktraceStatus};
+
+
+
+/// Delay freeing blocks, keeping the most recently freed ones poisoned in quarantine to catch writes after free.
+///
+/// [`fat::Free::free`] fills the block with `0xDD` and holds onto it instead of freeing it through `A`.
+/// Once more than `max_blocks` blocks, or more than `max_bytes` bytes, are quarantined, the oldest blocks are checked and released to `A`.
+/// Any byte no longer `0xDD` means something wrote to the block after it was freed - this is reported with the
+/// `#[track_caller]` [`Location`] of the original free.
+/// With the `std` feature, a [`Backtrace`] of the free is also [captured](Backtrace::capture) (subject to the usual `RUST_BACKTRACE` / `RUST_LIB_BACKTRACE` environment variables) -
+/// useful when the [`Location`] is just a generic free site, like [`ABox`](crate::boxed::ABox)'s [`Drop`].
+///
+/// *   [`fat::Realloc`] always allocates a new block, copies, and frees the old block into quarantine - as `A`'s own realloc would free moved blocks unchecked.
+/// *   Zero sized blocks are freed immediately, as there's nothing to poison.
+/// *   Freeing a block that's still in quarantine is reported as a double free.
+/// *   Dropping the `Quarantine`, or calling [`flush`](Self::flush), checks and releases every quarantined block.
+///
+/// Only [`meta`] and [`fat`] traits are implemented, as [`thin`] frees have no size to poison or check.
+///
+/// ### Example
+/// ```rust,should_panic
+/// # use ialloc::allocator::{alloc::Global, debug::Quarantine};
+/// # use ialloc::fat::*;
+/// # use core::alloc::Layout;
+/// let alloc = Quarantine::new(Global, 64, 64 * 1024);
+/// let layout = Layout::new::<u32>();
+/// let ptr = alloc.alloc_uninit(layout).unwrap();
+/// unsafe { alloc.free(ptr, layout) };
+/// unsafe { ptr.cast::<u32>().write(42) }; // oops, use after free
+/// alloc.flush(); // panics: "...was written to after being freed at src/main.rs:..."
+/// ```
+pub struct Quarantine<A: fat::Free> {
+    allocator:  A,
+    max_blocks: usize,
+    max_bytes:  usize,
+    state:      Locked<RefCell<State>>,
+}
+
+struct State {
+    blocks:     VecDeque<Block>,
+    addresses:  BTreeSet<usize>, // of `blocks` - ZSTs aren't quarantined, so these are unique
+    bytes:      usize,
+}
+
+struct Block {
+    ptr:        AllocNN,
+    layout:     Layout,
+    freed_at:   &'static Location<'static>,
+    #[cfg(feature = "std")] backtrace: Backtrace,
+}
+
+impl Display for Block {
+    /// Where this block was freed
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.freed_at)?;
+        #[cfg(feature = "std")] if self.backtrace.status() == BacktraceStatus::Captured { write!(fmt, "\n{}", self.backtrace)?; }
+        Ok(())
+    }
+}
+
+// SAFETY: ✔️ a quarantined block is exclusively owned by the `Quarantine` until it's released to `A`
+unsafe impl Send for Block {}
+
+impl<A: fat::Free> Quarantine<A> {
+    const POISON : u8 = 0xDD;
+
+    /// Quarantine up to `max_blocks` freed blocks, totaling at most `max_bytes` bytes, before releasing them to `allocator`.
+    pub const fn new(allocator: A, max_blocks: usize, max_bytes: usize) -> Self {
+        Self { allocator, max_blocks, max_bytes, state: Locked::new(RefCell::new(State { blocks: VecDeque::new(), addresses: BTreeSet::new(), bytes: 0 })) }
+    }
+
+    /// Check and release every quarantined block to the underlying allocator.
+    #[track_caller] pub fn flush(&self) { self.evict(|_| true) }
+
+    /// Check and release the oldest quarantined blocks to the underlying allocator, for as long as `evict` returns `true`.
+    #[track_caller] fn evict(&self, mut evict: impl FnMut(&State) -> bool) {
+        loop {
+            // SAFETY: ✔️ `state` doesn't escape
+            let block = unsafe { self.state.with(|state| {
+                let mut state = state.borrow_mut();
+                if !evict(&state) { return None }
+                let block = state.blocks.pop_front()?;
+                state.addresses.remove(&(block.ptr.as_ptr() as usize));
+                state.bytes -= block.layout.size();
+                Some(block)
+            })};
+            let Some(block) = block else { return };
+
+            // SAFETY: ✔️ `block` was filled with `POISON` when quarantined, and remains allocated until released below
+            let poisoned = unsafe { core::slice::from_raw_parts(block.ptr.as_ptr().cast::<u8>(), block.layout.size()) };
+            if poisoned.iter().any(|b| *b != Self::POISON) { bug::ub::written_after_free(block.ptr, block.layout, &block) }
+            // SAFETY: ✔️ `block` was allocated by `self.allocator` with `block.layout`, and was only quarantined instead of being freed
+            unsafe { self.allocator.free(block.ptr, block.layout) }
+        }
+    }
+}
+
+impl<A: fat::Free> Drop for Quarantine<A> {
+    fn drop(&mut self) {
+        #[cfg(feature = "std")] if std::thread::panicking() { return } // avoid a double panic abort hiding the original panic
+        self.flush();
+    }
+}
+
+impl<A: fat::Free> Debug for Quarantine<A> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        // SAFETY: ✔️ `state` doesn't escape
+        let (blocks, bytes) = unsafe { self.state.with(|state| { let state = state.borrow(); (state.blocks.len(), state.bytes) }) };
+        fmt.debug_struct("Quarantine")
+            .field("blocks",        &blocks)
+            .field("bytes",         &util::bytes::Pretty(bytes))
+            .field("max_blocks",    &self.max_blocks)
+            .field("max_bytes",     &util::bytes::Pretty(self.max_bytes))
+            .finish_non_exhaustive()
+    }
+}
+
+
+
+// meta::*
+
+impl<A: fat::Free> Meta for Quarantine<A> {
+    type Error                  = A::Error;
+    const MAX_ALIGN : Alignment = A::MAX_ALIGN;
+    const MAX_SIZE  : usize     = A::MAX_SIZE;
+    const ZST_SUPPORTED : bool  = A::ZST_SUPPORTED;
+}
+
+impl<A: fat::Free + ZstSupported> ZstSupported for Quarantine<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: fat::Free + ZstInfalliable> ZstInfalliable for Quarantine<A> {}
+
+// SAFETY: ✔️ per underlying allocator - quarantined blocks are still owned by `A`, and are no longer valid to pass to `self` anyways
+unsafe impl<A: fat::Free + Owns> Owns for Quarantine<A> {
+    fn owns(&self, ptr: AllocNN) -> bool { self.allocator.owns(ptr) }
+}
+
+
+
+// fat::*
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: fat::Alloc + fat::Free> fat::Alloc for Quarantine<A> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> { self.allocator.alloc_uninit(layout) }
+    fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> { self.allocator.alloc_zeroed(layout) }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ blocks are eventually freed through `A` with the same layout, or leaked if the `Quarantine` is leaked
+//
+unsafe impl<A: fat::Free> fat::Free for Quarantine<A> {
+    #[track_caller] unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        if layout.size() == 0 {
+            // SAFETY: ✔️ same trait, same prereqs
+            return unsafe { self.allocator.free(ptr, layout) };
+        }
+
+        // SAFETY: ✔️ `ptr` is valid for `layout` per `fat::Free::free`'s documented safety preconditions
+        unsafe { ptr.as_ptr().cast::<u8>().write_bytes(Self::POISON, layout.size()) };
+        let block = Block { ptr, layout, freed_at: Location::caller(), #[cfg(feature = "std")] backtrace: Backtrace::capture() };
+
+        // SAFETY: ✔️ `state` doesn't escape
+        let double_free = unsafe { self.state.with(|state| {
+            let mut state = state.borrow_mut();
+            if !state.addresses.insert(ptr.as_ptr() as usize) { return true }
+            state.bytes += layout.size();
+            state.blocks.push_back(block);
+            false
+        })};
+        if double_free { bug::ub::freed_ptr_for_allocator(ptr) }
+
+        self.evict(|state| state.blocks.len() > self.max_blocks || state.bytes > self.max_bytes);
+    }
+}
+
+// SAFETY: ✔️ default Realloc impl is soundly implemented in terms of Alloc+Free - and always quarantines the old block via our own `fat::Free`
+unsafe impl<A: fat::Alloc + fat::Free> fat::Realloc for Quarantine<A> {}
+
+#[cfg(allocator_api = "1.50")] #[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, Quarantine};
+
+    impls! {
+        unsafe impl[A: fat::Alloc + fat::Free] core::alloc::Allocator(unstable 1.50) for Quarantine<A> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(test)] mod backing_global {
+    use super::*;
+    use crate::allocator::{alloc::Global, debug::Tracking};
+    use crate::fat::*;
+
+    const U32 : Layout = Layout::new::<u32>();
+
+    #[test] fn evicts_oldest_blocks() {
+        let alloc = Quarantine::new(Tracking::new(Global), 2, 12);
+        let a = alloc.alloc_uninit(U32).unwrap();
+        let b = alloc.alloc_uninit(U32).unwrap();
+        let c = alloc.alloc_uninit(Layout::new::<[u32; 2]>()).unwrap();
+        // SAFETY: ✔️ `a` and `b` belong to `alloc` and were allocated with `U32`
+        unsafe { alloc.free(a, U32); alloc.free(b, U32) };
+        assert_eq!(0, alloc.allocator.stats().frees);
+        // SAFETY: ✔️ `c` belongs to `alloc` and was allocated with `[u32; 2]` - exceeding the byte budget, evicting `a`
+        unsafe { alloc.free(c, Layout::new::<[u32; 2]>()) };
+        assert_eq!(1, alloc.allocator.stats().frees);
+        alloc.flush();
+        assert_eq!(3, alloc.allocator.stats().frees);
+    }
+
+    #[test] fn realloc_quarantines() {
+        let alloc = Quarantine::new(Tracking::new(Global), 16, usize::MAX);
+        let a = alloc.alloc_uninit(U32).unwrap();
+        // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `U32`
+        let b = unsafe { alloc.realloc_uninit(a, U32, Layout::new::<u64>()) }.unwrap();
+        assert_eq!(0, alloc.allocator.stats().frees);
+        // SAFETY: ✔️ `b` belongs to `alloc` and was reallocated with `u64`
+        unsafe { alloc.free(b, Layout::new::<u64>()) };
+    }
+
+    #[cfg(feature = "std")] #[test] fn use_after_free() {
+        let alloc = Quarantine::new(Global, 16, usize::MAX);
+        let ptr = alloc.alloc_uninit(U32).unwrap();
+        // SAFETY: ✔️ `ptr` belongs to `alloc` and was allocated with `U32` - freed through `&Quarantine`'s blanket impl, which should still report this line
+        unsafe { Free::free(&&alloc, ptr, U32) }; let line = line!();
+        // SAFETY: ❌ intentionally writing to `ptr` after freeing it to test the bug report - it's still allocated from `Global` while quarantined
+        unsafe { ptr.cast::<u32>().write(42) };
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| alloc.flush())).unwrap_err();
+        let message = panic.downcast_ref::<std::string::String>().unwrap();
+        assert!(message.contains(&std::format!("was written to after being freed at {}:{line}:", file!())), "{message}");
+    }
+
+    #[test] #[should_panic = "but it was already freed"] fn double_free() {
+        let alloc = Quarantine::new(Global, 16, usize::MAX);
+        let ptr = alloc.alloc_uninit(U32).unwrap();
+        // SAFETY: ❌ intentionally freeing `ptr` twice to test the bug report
+        unsafe { alloc.free(ptr, U32); alloc.free(ptr, U32) };
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(Quarantine::new(Global, 16, 1024)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(Quarantine::new(Global, 16, 1024)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(Quarantine::new(Global, 16, 1024)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(Quarantine::new(Global, 16, 1024)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(Quarantine::new(Global, 16, 1024)) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use super::*;
+    use crate::allocator::c::Malloc;
+
+    #[test] fn fat_alignment()          { fat::test::alignment(Quarantine::new(Malloc, 16, 1024)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(Quarantine::new(Malloc, 16, 1024)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(Quarantine::new(Malloc, 16, 1024)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(Quarantine::new(Malloc, 16, 1024)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_conservative(Quarantine::new(Malloc, 16, 1024)) }
+}
@@ -0,0 +1,341 @@
+use crate::*;
+use crate::meta::*;
+use crate::util::bytes::Pretty;
+
+use core::alloc::Layout;
+use core::fmt::{self, Debug, Formatter};
+use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+
+
+/// Count allocations, frees, reallocs, failures, and live/peak bytes made through an allocator.
+///
+/// All [`meta`], [`thin`], and [`fat`] traits of `A` are forwarded.
+/// Byte counts are based on `layout.size()`, so only [`fat`] calls contribute bytes:
+/// [`thin`] calls are counted, but contribute no bytes.
+/// Freeing a [`thin`] allocation through [`fat`], or vice versa, may skew live byte counts.
+///
+/// ### Example
+/// ```rust
+/// # use ialloc::allocator::{alloc::Global, debug::Tracking};
+/// # use ialloc::boxed::ABox;
+/// let alloc = Tracking::new(Global);
+/// let a = ABox::try_new_in([0u8; 64], &alloc).unwrap();
+/// let b = ABox::try_new_in([0u8; 32], &alloc).unwrap();
+/// drop(a);
+/// let stats = alloc.stats();
+/// assert_eq!(stats.live_allocs, 1);
+/// assert_eq!(stats.live_bytes,  32);
+/// assert_eq!(stats.peak_bytes,  96);
+/// # drop(b);
+/// ```
+pub struct Tracking<A> {
+    allocator:      A,
+    live_allocs:    AtomicUsize,
+    live_bytes:     AtomicUsize,
+    peak_bytes:     AtomicUsize,
+    allocs:         AtomicUsize,
+    frees:          AtomicUsize,
+    reallocs:       AtomicUsize,
+    failures:       AtomicUsize,
+}
+
+/// A snapshot of [`Tracking`]'s counters, as returned by [`Tracking::stats`].
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)] pub struct TrackingStats {
+    /// Allocations made but not yet freed.
+    pub live_allocs:    usize,
+    /// Bytes allocated but not yet freed.
+    pub live_bytes:     usize,
+    /// The most [`live_bytes`](Self::live_bytes) there have ever been.
+    pub peak_bytes:     usize,
+    /// Successful allocations made.
+    pub allocs:         usize,
+    /// Allocations freed.
+    pub frees:          usize,
+    /// Successful reallocations made.
+    pub reallocs:       usize,
+    /// Failed allocations and reallocations.
+    pub failures:       usize,
+}
+
+impl Debug for TrackingStats {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("TrackingStats")
+            .field("live_allocs",   &self.live_allocs)
+            .field("live_bytes",    &Pretty(self.live_bytes))
+            .field("peak_bytes",    &Pretty(self.peak_bytes))
+            .field("allocs",        &self.allocs)
+            .field("frees",         &self.frees)
+            .field("reallocs",      &self.reallocs)
+            .field("failures",      &self.failures)
+            .finish()
+    }
+}
+
+impl<A> Tracking<A> {
+    /// Start tracking allocations made through `allocator`.
+    pub const fn new(allocator: A) -> Self {
+        Self {
+            allocator,
+            live_allocs:    AtomicUsize::new(0),
+            live_bytes:     AtomicUsize::new(0),
+            peak_bytes:     AtomicUsize::new(0),
+            allocs:         AtomicUsize::new(0),
+            frees:          AtomicUsize::new(0),
+            reallocs:       AtomicUsize::new(0),
+            failures:       AtomicUsize::new(0),
+        }
+    }
+
+    /// Unwrap the underlying allocator.
+    pub fn into_inner(self) -> A { self.allocator }
+
+    /// Take a snapshot of the current statistics.
+    ///
+    /// Counters are read individually, so a snapshot taken while other threads are (de)allocating may be slightly inconsistent.
+    pub fn stats(&self) -> TrackingStats {
+        TrackingStats {
+            live_allocs:    self.live_allocs.load(Relaxed),
+            live_bytes:     self.live_bytes .load(Relaxed),
+            peak_bytes:     self.peak_bytes .load(Relaxed),
+            allocs:         self.allocs     .load(Relaxed),
+            frees:          self.frees      .load(Relaxed),
+            reallocs:       self.reallocs   .load(Relaxed),
+            failures:       self.failures   .load(Relaxed),
+        }
+    }
+
+    /// Reset [`peak_bytes`](TrackingStats::peak_bytes) to the current [`live_bytes`](TrackingStats::live_bytes).
+    ///
+    /// Useful for measuring the high water mark of a specific phase of a program.
+    pub fn reset_peak(&self) { self.peak_bytes.store(self.live_bytes.load(Relaxed), Relaxed) }
+
+    fn add_bytes(&self, bytes: usize) {
+        let live = self.live_bytes.fetch_add(bytes, Relaxed).wrapping_add(bytes);
+        self.peak_bytes.fetch_max(live, Relaxed);
+    }
+
+    fn sub_bytes(&self, bytes: usize) {
+        let _ = self.live_bytes.fetch_update(Relaxed, Relaxed, |live| Some(live.saturating_sub(bytes))); // saturate if thin/fat mixing skewed counts
+    }
+
+    fn on_alloc<T>(&self, result: Result<T, A::Error>, bytes: impl FnOnce(&T) -> usize) -> Result<T, A::Error> where A: Meta {
+        match result.as_ref() {
+            Ok(ptr) => {
+                self.allocs.fetch_add(1, Relaxed);
+                self.live_allocs.fetch_add(1, Relaxed);
+                self.add_bytes(bytes(ptr));
+            },
+            Err(_) => { self.failures.fetch_add(1, Relaxed); },
+        }
+        result
+    }
+
+    fn on_free(&self, bytes: usize) {
+        self.frees.fetch_add(1, Relaxed);
+        self.live_allocs.fetch_sub(1, Relaxed);
+        self.sub_bytes(bytes);
+    }
+
+    fn on_realloc(&self, result: Result<AllocNN, A::Error>, old_bytes: usize, new_bytes: impl FnOnce(AllocNN) -> usize) -> Result<AllocNN, A::Error> where A: Meta {
+        match result {
+            Ok(ptr) => {
+                self.reallocs.fetch_add(1, Relaxed);
+                let new_bytes = new_bytes(ptr);
+                if new_bytes >= old_bytes { self.add_bytes(new_bytes - old_bytes) } else { self.sub_bytes(old_bytes - new_bytes) }
+            },
+            Err(_) => { self.failures.fetch_add(1, Relaxed); },
+        }
+        result
+    }
+}
+
+impl<A: Default> Default for Tracking<A> { fn default() -> Self { Self::new(A::default()) } }
+impl<A> Debug for Tracking<A> { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { fmt.debug_struct("Tracking").field("stats", &self.stats()).finish_non_exhaustive() } }
+impl<A> From<A> for Tracking<A> { fn from(allocator: A) -> Self { Self::new(allocator) } }
+
+
+
+// meta::*
+
+impl<A: Meta> Meta for Tracking<A> {
+    type Error                  = A::Error;
+    const MAX_ALIGN : Alignment = A::MAX_ALIGN;
+    const MAX_SIZE  : usize     = A::MAX_SIZE;
+    const ZST_SUPPORTED : bool  = A::ZST_SUPPORTED;
+}
+
+impl<A: ZstSupported> ZstSupported for Tracking<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: ZstInfalliable> ZstInfalliable for Tracking<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: Owns> Owns for Tracking<A> {
+    fn owns(&self, ptr: AllocNN) -> bool { self.allocator.owns(ptr) }
+}
+
+
+
+// thin::*
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::Alloc> thin::Alloc for Tracking<A> {
+    fn alloc_uninit(&self, size: usize) -> Result<AllocNN,  Self::Error> { self.on_alloc(self.allocator.alloc_uninit(size), |_| 0) }
+    fn alloc_zeroed(&self, size: usize) -> Result<AllocNN0, Self::Error> { self.on_alloc(self.allocator.alloc_zeroed(size), |_| 0) }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::Free> thin::Free for Tracking<A> {
+    unsafe fn free(&self, ptr: AllocNN) {
+        self.on_free(0);
+        unsafe { self.allocator.free(ptr) }
+    }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::Realloc> thin::Realloc for Tracking<A> {
+    const CAN_REALLOC_ZEROED : bool = A::CAN_REALLOC_ZEROED;
+
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        self.on_realloc(unsafe { self.allocator.realloc_uninit(ptr, new_size) }, 0, |_| 0)
+    }
+
+    unsafe fn realloc_zeroed(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        self.on_realloc(unsafe { self.allocator.realloc_zeroed(ptr, new_size) }, 0, |_| 0)
+    }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::SizeOf> thin::SizeOf for Tracking<A> {
+    unsafe fn size_of(&self, ptr: AllocNN) -> usize { unsafe { self.allocator.size_of(ptr) } }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: thin::SizeOfDebug> thin::SizeOfDebug for Tracking<A> {
+    unsafe fn size_of_debug(&self, ptr: AllocNN) -> Option<usize> { unsafe { self.allocator.size_of_debug(ptr) } }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: fat::Alloc> fat::Alloc for Tracking<A> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN,  Self::Error> { self.on_alloc(self.allocator.alloc_uninit(layout), |_| layout.size()) }
+    fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> { self.on_alloc(self.allocator.alloc_zeroed(layout), |_| layout.size()) }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: fat::Free> fat::Free for Tracking<A> {
+    unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        self.on_free(layout.size());
+        unsafe { self.allocator.free(ptr, layout) }
+    }
+}
+
+#[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: fat::Realloc> fat::Realloc for Tracking<A> {
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        self.on_realloc(unsafe { self.allocator.realloc_uninit(ptr, old_layout, new_layout) }, old_layout.size(), |_| new_layout.size())
+    }
+
+    unsafe fn realloc_zeroed(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        self.on_realloc(unsafe { self.allocator.realloc_zeroed(ptr, old_layout, new_layout) }, old_layout.size(), |_| new_layout.size())
+    }
+}
+
+#[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, Tracking};
+
+    impls! {
+        unsafe impl[A: fat::Realloc] core::alloc::GlobalAlloc for Tracking<A> => ialloc::fat::Realloc;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl[A: fat::Realloc] core::alloc::Allocator(unstable 1.50) for Tracking<A> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(all(test, feature = "alloc"))] mod backing_global {
+    use super::*;
+    use crate::allocator::alloc::Global;
+    use crate::allocator::debug::FailAfter;
+
+    #[test] fn counts() {
+        use crate::fat::*;
+        let alloc = Tracking::new(Global);
+        let l16 = Layout::new::<[u8; 16]>();
+        let l64 = Layout::new::<[u8; 64]>();
+
+        let a = alloc.alloc_uninit(l16).unwrap();
+        let b = alloc.alloc_zeroed(l64).unwrap();
+        // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `l16`
+        let a = unsafe { alloc.realloc_uninit(a, l16, l64) }.unwrap();
+        assert_eq!(alloc.stats(), TrackingStats { live_allocs: 2, live_bytes: 128, peak_bytes: 128, allocs: 2, frees: 0, reallocs: 1, failures: 0 });
+
+        // SAFETY: ✔️ `b` belongs to `alloc` and was allocated with `l64`
+        unsafe { alloc.free(b.cast(), l64) };
+        alloc.reset_peak();
+        // SAFETY: ✔️ `a` belongs to `alloc` and was reallocated with `l64`
+        unsafe { alloc.free(a, l64) };
+        assert_eq!(alloc.stats(), TrackingStats { live_allocs: 0, live_bytes: 0, peak_bytes: 64, allocs: 2, frees: 2, reallocs: 1, failures: 0 });
+    }
+
+    #[test] fn failures() {
+        use crate::fat::*;
+        let alloc = Tracking::new(FailAfter::new(Global, 0));
+        assert!(alloc.alloc_uninit(Layout::new::<[u8; 16]>()).is_err());
+        assert_eq!(alloc.stats(), TrackingStats { failures: 1, ..TrackingStats::default() });
+    }
+
+    #[test] fn debug() {
+        let alloc = Tracking::new(Global);
+        let _a = crate::boxed::ABox::try_new_in([0u8; 20000], &alloc).unwrap();
+        assert_eq!(
+            alloc::format!("{:?}", alloc.stats()),
+            "TrackingStats { live_allocs: 1, live_bytes: 19 KiB, peak_bytes: 19 KiB, allocs: 1, frees: 0, reallocs: 0, failures: 0 }",
+        );
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(Tracking::new(Global)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(Tracking::new(Global)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(Tracking::new(Global)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(Tracking::new(Global)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(Tracking::new(Global)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(Tracking::new(Global)) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use super::*;
+    use crate::allocator::c::Malloc;
+
+    #[test] fn thin_counts() {
+        use crate::thin::*;
+        let alloc = Tracking::new(Malloc);
+        let a = alloc.alloc_uninit(16).unwrap();
+        // SAFETY: ✔️ `a` belongs to `alloc`
+        let a = unsafe { alloc.realloc_uninit(a, 64) }.unwrap();
+        // SAFETY: ✔️ `a` belongs to `alloc`
+        unsafe { alloc.free(a) };
+        let stats = alloc.stats();
+        assert_eq!((stats.live_allocs, stats.live_bytes, stats.allocs, stats.frees, stats.reallocs), (0, 0, 1, 1, 1));
+    }
+
+    #[test] fn thin_alignment()         { thin::test::alignment(Tracking::new(Malloc)) }
+    #[test] fn thin_edge_case_sizes()   { thin::test::edge_case_sizes(Tracking::new(Malloc)) }
+    #[test] fn thin_nullable()          { thin::test::nullable(Tracking::new(Malloc)) }
+    #[test] fn thin_uninit_realloc()    { thin::test::uninit_realloc(Tracking::new(Malloc)) }
+    #[test] fn thin_zeroed()            { thin::test::zeroed_alloc(Tracking::new(Malloc)) }
+    #[test] fn thin_zeroed_realloc()    { thin::test::zeroed_realloc(Tracking::new(Malloc)) }
+    #[test] fn thin_zst_support()       { thin::test::zst_supported_conservative(Tracking::new(Malloc)) }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(Tracking::new(Malloc)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(Tracking::new(Malloc)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(Tracking::new(Malloc)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(Tracking::new(Malloc)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(Tracking::new(Malloc)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_conservative(Tracking::new(Malloc)) }
+}
@@ -0,0 +1,226 @@
+use crate::*;
+use crate::meta::*;
+use super::live_records::{Key, LiveRecords};
+
+use core::alloc::Layout;
+use core::fmt::{self, Debug, Formatter};
+
+
+
+/// Record the [`Layout`] of every live [`fat`] allocation, and panic if it's freed or reallocated with a different one.
+///
+/// [`fat::Free::free`] and [`fat::Realloc`] require the `layout` passed to exactly match that of the last successful (re)allocation.
+/// Most allocators ignore it entirely - but sized allocators (e.g. jemalloc's `sdallocx`) silently corrupt their heaps on a mismatch,
+/// which makes such bugs in generic code easy to miss until it's run with the wrong allocator.
+///
+/// Panics (via `bug::ub`) before forwarding to `A` on:
+/// *   A mismatched size or alignment.
+/// *   A double free (or realloc after free) of one of the last 1024 freed addresses.
+/// *   A pointer that never belonged to this allocator (or was freed longer ago.)
+///
+/// Only [`meta`] and [`fat`] traits are forwarded, as [`thin`] allocations have no [`Layout`] to validate.
+///
+/// ### Example
+/// ```rust,should_panic
+/// # use ialloc::allocator::{alloc::Global, debug::ValidateLayouts};
+/// # use ialloc::fat::*;
+/// # use core::alloc::Layout;
+/// let alloc = ValidateLayouts::new(Global);
+/// let ptr = alloc.alloc_uninit(Layout::new::<u32>()).unwrap();
+/// unsafe { alloc.free(ptr, Layout::new::<u64>()) }; // panics: "...was (re)allocated with Layout { size: 4, align: 4 ... } and then passed Layout { size: 8, ..."
+/// ```
+pub struct ValidateLayouts<A> {
+    allocator:  A,
+    live:       LiveRecords<Layout>,
+}
+
+impl<A> ValidateLayouts<A> {
+    /// Start validating the layouts passed to `allocator`.
+    pub const fn new(allocator: A) -> Self { Self { allocator, live: LiveRecords::new() } }
+
+    /// Unwrap the underlying allocator.
+    pub fn into_inner(self) -> A { self.allocator }
+
+    /// Find the record of `ptr` allocated with `layout`, or panic.
+    #[track_caller] fn validate(&self, ptr: AllocNN, layout: Layout) -> Key {
+        match self.live.find(ptr, |l| *l == layout) {
+            Ok(key)                                     => key,
+            Err(Some(allocated))                        => bug::ub::mismatched_layout_for_allocator(ptr, allocated, layout),
+            Err(None) if self.live.was_freed(ptr)       => bug::ub::freed_ptr_for_allocator(ptr),
+            Err(None)                                   => bug::ub::invalid_ptr_for_allocator(ptr),
+        }
+    }
+}
+
+impl<A: Default> Default for ValidateLayouts<A> { fn default() -> Self { Self::new(A::default()) } }
+impl<A> From<A> for ValidateLayouts<A> { fn from(allocator: A) -> Self { Self::new(allocator) } }
+
+impl<A> Debug for ValidateLayouts<A> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("ValidateLayouts").field("live", &self.live.len()).finish_non_exhaustive()
+    }
+}
+
+
+
+// meta::*
+
+impl<A: Meta> Meta for ValidateLayouts<A> {
+    type Error                  = A::Error;
+    const MAX_ALIGN : Alignment = A::MAX_ALIGN;
+    const MAX_SIZE  : usize     = A::MAX_SIZE;
+    const ZST_SUPPORTED : bool  = A::ZST_SUPPORTED;
+}
+
+impl<A: ZstSupported> ZstSupported for ValidateLayouts<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: ZstInfalliable> ZstInfalliable for ValidateLayouts<A> {}
+
+// SAFETY: ✔️ per underlying allocator
+unsafe impl<A: Owns> Owns for ValidateLayouts<A> {
+    fn owns(&self, ptr: AllocNN) -> bool { self.allocator.owns(ptr) }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: fat::Alloc> fat::Alloc for ValidateLayouts<A> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> {
+        let ptr = self.allocator.alloc_uninit(layout)?;
+        self.live.insert(ptr, layout);
+        Ok(ptr)
+    }
+
+    fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> {
+        let ptr = self.allocator.alloc_zeroed(layout)?;
+        self.live.insert(ptr.cast(), layout);
+        Ok(ptr)
+    }
+}
+
+// SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: fat::Free> fat::Free for ValidateLayouts<A> {
+    #[track_caller] unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        let key = self.validate(ptr, layout);
+        self.live.remove(key);
+        // SAFETY: ✔️ same prereqs, which `validate` just checked
+        unsafe { self.allocator.free(ptr, layout) }
+    }
+}
+
+// SAFETY: ✔️ implemented against same traits with same prereqs
+unsafe impl<A: fat::Realloc> fat::Realloc for ValidateLayouts<A> {
+    #[track_caller] unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        let key = self.validate(ptr, old_layout);
+        // SAFETY: ✔️ same prereqs, which `validate` just checked
+        let new = unsafe { self.allocator.realloc_uninit(ptr, old_layout, new_layout) }?;
+        self.live.remove(key);
+        self.live.insert(new, new_layout);
+        Ok(new)
+    }
+
+    #[track_caller] unsafe fn realloc_zeroed(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        let key = self.validate(ptr, old_layout);
+        // SAFETY: ✔️ same prereqs, which `validate` just checked
+        let new = unsafe { self.allocator.realloc_zeroed(ptr, old_layout, new_layout) }?;
+        self.live.remove(key);
+        self.live.insert(new, new_layout);
+        Ok(new)
+    }
+}
+
+#[cfg(allocator_api = "1.50")] #[no_implicit_prelude] mod cleanroom {
+    use super::{impls, fat, ValidateLayouts};
+
+    impls! {
+        unsafe impl[A: fat::Realloc] core::alloc::Allocator(unstable 1.50) for ValidateLayouts<A> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[cfg(test)] mod backing_global {
+    use super::*;
+    use crate::allocator::alloc::Global;
+    use crate::fat::*;
+
+    const U32 : Layout = Layout::new::<u32>();
+
+    #[test] #[should_panic = "and then passed Layout { size: 8"] fn mismatched_size() {
+        let alloc = ValidateLayouts::new(Global);
+        let ptr = alloc.alloc_uninit(U32).unwrap();
+        // SAFETY: ❌ intentionally freeing with the wrong layout to test the bug report
+        unsafe { alloc.free(ptr, Layout::from_size_align(8, 4).unwrap()) };
+    }
+
+    #[test] #[should_panic = "and then passed Layout { size: 4, align: 1"] fn mismatched_align() {
+        let alloc = ValidateLayouts::new(Global);
+        let ptr = alloc.alloc_uninit(U32).unwrap();
+        // SAFETY: ❌ intentionally freeing with the wrong layout to test the bug report
+        unsafe { alloc.free(ptr, Layout::from_size_align(4, 1).unwrap()) };
+    }
+
+    #[test] #[should_panic = "and then passed Layout { size: 4"] fn mismatched_realloc() {
+        let alloc = ValidateLayouts::new(Global);
+        let ptr = alloc.alloc_uninit(U32).unwrap();
+        // SAFETY: ✔️ `ptr` belongs to `alloc` and was allocated with `U32`
+        let ptr = unsafe { alloc.realloc_uninit(ptr, U32, Layout::new::<u64>()) }.unwrap();
+        // SAFETY: ❌ intentionally freeing with the pre-realloc layout to test the bug report
+        unsafe { alloc.free(ptr, U32) };
+    }
+
+    #[test] #[should_panic = "but it was already freed"] fn double_free() {
+        let alloc = ValidateLayouts::new(Global);
+        let ptr = alloc.alloc_uninit(U32).unwrap();
+        // SAFETY: ❌ intentionally freeing `ptr` twice to test the bug report
+        unsafe { alloc.free(ptr, U32); alloc.free(ptr, U32) };
+    }
+
+    #[test] #[should_panic = "doesn't belong to this allocator"] fn foreign() {
+        let alloc = ValidateLayouts::new(Global);
+        let ptr = Global.alloc_uninit(U32).unwrap();
+        // SAFETY: ❌ intentionally freeing a pointer belonging to another allocator to test the bug report
+        unsafe { alloc.free(ptr, U32) };
+    }
+
+    #[test] fn freed_history_is_bounded() {
+        let alloc = ValidateLayouts::<Global>::new(Global);
+        for _ in 0 .. 2 * LiveRecords::<Layout>::FREED_HISTORY {
+            let ptr = alloc.alloc_uninit(U32).unwrap();
+            // SAFETY: ✔️ `ptr` belongs to `alloc` and was allocated with `U32`
+            unsafe { alloc.free(ptr, U32) };
+        }
+        assert_eq!(LiveRecords::<Layout>::FREED_HISTORY, alloc.live.freed_len());
+    }
+
+    #[test] fn zsts() {
+        let alloc = ValidateLayouts::new(Global);
+        let zst = Layout::new::<()>();
+        let a = alloc.alloc_uninit(zst).unwrap();
+        let b = alloc.alloc_uninit(zst).unwrap();
+        // SAFETY: ✔️ `a` and `b` belong to `alloc` and were allocated with `zst`
+        unsafe { alloc.free(a, zst); alloc.free(b, zst) };
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(ValidateLayouts::new(Global)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(ValidateLayouts::new(Global)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(ValidateLayouts::new(Global)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(ValidateLayouts::new(Global)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(ValidateLayouts::new(Global)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(ValidateLayouts::new(Global)) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use super::*;
+    use crate::allocator::c::Malloc;
+
+    #[test] fn fat_alignment()          { fat::test::alignment(ValidateLayouts::new(Malloc)) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(ValidateLayouts::new(Malloc)) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(ValidateLayouts::new(Malloc)) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(ValidateLayouts::new(Malloc)) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(ValidateLayouts::new(Malloc)) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_conservative(ValidateLayouts::new(Malloc)) }
+}
@@ -1,4 +1,15 @@
-//! [`Bump`], [`FixedPoolLinearProbe`]
+//! [`Arena`], [`AtomicBump`], [`Buddy`], [`Bump`], [`DoubleStack`], [`FixedPoolFreeList`], [`FixedPoolLinearProbe`], [`FrameRing`], [`PoolFreeList`], [`Slab`], [`Stack`], [`Tlsf`]
 
-mod bump;           pub use bump::*;
-mod fixed_pool;     pub use fixed_pool::*;
+mod arena;                  pub use arena::*;
+#[cfg(target_has_atomic = "ptr")]
+mod atomic_bump;            pub use atomic_bump::*;
+mod buddy;                  pub use buddy::*;
+mod bump;                   pub use bump::*;
+mod double_stack;           pub use double_stack::*;
+mod fixed_pool;             pub use fixed_pool::*;
+mod fixed_pool_free_list;   pub use fixed_pool_free_list::*;
+mod frame_ring;             pub use frame_ring::*;
+mod pool_free_list;         pub use pool_free_list::*;
+mod slab;                   pub use slab::*;
+mod stack;                  pub use stack::*;
+mod tlsf;                   pub use tlsf::*;
@@ -0,0 +1,309 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::fmt::{self, Debug, Formatter};
+use core::mem::{align_of, size_of, MaybeUninit};
+use core::ptr::NonNull;
+
+
+
+/// Bump-allocate from chunks of memory allocated on demand from a `Backing` allocator
+///
+/// Unlike [`Bump`](super::Bump), which is limited to a single borrowed buffer, an `Arena` allocates another chunk when the current one runs out.
+/// Chunk sizes grow geometrically (each new chunk is at least twice the size of the previous one) to keep the number of chunks `O(log n)`.
+///
+/// Individual frees are (mostly) no-ops:  memory is only reclaimed in bulk by [`reset`](Self::reset), which keeps all chunks around for reuse,
+/// or when the `Arena` is dropped, which returns all chunks to `Backing`.
+/// The one exception:  reallocating the most recent allocation grows or shrinks it in place.
+pub struct Arena<Backing: fat::Alloc + fat::Free> {
+    backing:    Backing,
+    first:      Cell<Option<NonNull<ChunkHeader>>>,
+    current:    Cell<Option<NonNull<ChunkHeader>>>,
+    used:       Cell<usize>, // bytes of `current` used, including the header
+    next_size:  Cell<usize>,
+    #[cfg(debug_assertions)] outstanding_allocs: Cell<usize>,
+}
+
+impl<Backing: fat::Alloc + fat::Free> Arena<Backing> {
+    const DEFAULT_CHUNK_SIZE : usize = 4096;
+
+    /// Create an empty arena, allocating chunks from `Backing::default()` as necessary.
+    pub fn new() -> Self where Backing : Default { Self::new_in(Backing::default()) }
+
+    /// Create an empty arena, allocating chunks from `backing` as necessary.
+    pub fn new_in(backing: Backing) -> Self { Self::with_chunk_size_in(backing, Self::DEFAULT_CHUNK_SIZE) }
+
+    /// Create an empty arena, allocating chunks (starting at `chunk_size` bytes, header included) from `backing` as necessary.
+    pub fn with_chunk_size_in(backing: Backing, chunk_size: usize) -> Self {
+        Self {
+            backing,
+            first:      Cell::new(None),
+            current:    Cell::new(None),
+            used:       Cell::new(0),
+            next_size:  Cell::new(chunk_size.max(2 * size_of::<ChunkHeader>())),
+            #[cfg(debug_assertions)] outstanding_allocs: Cell::new(0),
+        }
+    }
+
+    /// Number of chunks currently allocated from `Backing`.
+    pub fn chunks(&self) -> usize {
+        let mut n = 0;
+        let mut chunk = self.first.get();
+        while let Some(c) = chunk {
+            n += 1;
+            // SAFETY: ✔️ all chunks reachable from `self.first` are live with initialized headers
+            chunk = unsafe { (*c.as_ptr()).next };
+        }
+        n
+    }
+
+    /// Release all allocations, keeping all chunks allocated from `Backing` for reuse.
+    ///
+    /// This takes `&mut self`, so no borrowing allocations (e.g. <code>[ABox](crate::boxed::ABox)&lt;T, &amp;Arena&gt;</code>) can still be alive.
+    ///
+    /// ### Panics
+    /// *   (debug builds only) If allocations are still outstanding - e.g. they were [`forget`](core::mem::forget)ten.
+    pub fn reset(&mut self) {
+        #[cfg(debug_assertions)] assert_eq!(0, self.outstanding_allocs.get(), "allocator::simple::Arena::reset: allocations are still outstanding");
+        self.current.set(self.first.get());
+        self.used.set(size_of::<ChunkHeader>());
+    }
+
+    /// Try to bump-allocate `layout` from `chunk`, assuming `used` bytes of it are already used.
+    ///
+    /// Returns the allocation, and the new value for `used`.
+    fn try_alloc_in(chunk: NonNull<ChunkHeader>, used: usize, layout: Layout) -> Option<(AllocNN, usize)> {
+        // SAFETY: ✔️ `chunk` is live with an initialized header
+        let size = unsafe { (*chunk.as_ptr()).size };
+        let base = chunk.as_ptr().cast::<MaybeUninit<u8>>();
+        let align_mask = layout.align() - 1;
+        let misalign = align_mask & (base as usize).wrapping_add(used);
+        let skip = layout.align().wrapping_sub(misalign) & align_mask;
+        let start = used.checked_add(skip)?;
+        let end = start.checked_add(layout.size())?;
+        if end > size { return None }
+        // SAFETY: ✔️ `start < end <= size`, so this stays within `chunk`
+        Some((unsafe { NonNull::new_unchecked(base.add(start)) }, end))
+    }
+
+    /// Allocate a new chunk from `self.backing` large enough to hold `layout`, and link it in after `self.current`.
+    fn grow(&self, layout: Layout) -> Result<NonNull<ChunkHeader>, ()> {
+        let min = size_of::<ChunkHeader>().checked_add(layout.align() - 1).and_then(|n| n.checked_add(layout.size())).ok_or(())?;
+        let size = self.next_size.get().max(min);
+        let chunk_layout = Layout::from_size_align(size, align_of::<ChunkHeader>()).map_err(|_| ())?;
+        let chunk = self.backing.alloc_uninit(chunk_layout).map_err(|_| ())?.cast::<ChunkHeader>();
+
+        let next = match self.current.get() {
+            // SAFETY: ✔️ `c` is live with an initialized header
+            Some(c) => unsafe { core::mem::replace(&mut (*c.as_ptr()).next, Some(chunk)) },
+            None    => { self.first.set(Some(chunk)); None },
+        };
+        // SAFETY: ✔️ `chunk` was just allocated with `chunk_layout`, so it's valid for writes of the header
+        unsafe { chunk.as_ptr().write(ChunkHeader { next, size }) };
+        self.next_size.set(size.saturating_mul(2));
+        Ok(chunk)
+    }
+}
+
+impl<Backing: fat::Alloc + fat::Free + Default> Default for Arena<Backing> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<Backing: fat::Alloc + fat::Free> Debug for Arena<Backing> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        #[cfg(    debug_assertions )] return write!(f, "Arena {{ chunks: {}, outstanding_allocs: {} }}", self.chunks(), self.outstanding_allocs.get());
+        #[cfg(not(debug_assertions))] return write!(f, "Arena {{ chunks: {}, outstanding_allocs: ?? }}", self.chunks());
+    }
+}
+
+impl<Backing: fat::Alloc + fat::Free> Drop for Arena<Backing> {
+    fn drop(&mut self) {
+        // Outstanding allocations are about to dangle - e.g. perhaps `ABox::into_inner` outlived the arena.
+        #[cfg(debug_assertions)] assert_eq!(0, self.outstanding_allocs.get(), "allocator::simple::Arena has outstanding allocations");
+
+        let mut chunk = self.first.take();
+        while let Some(c) = chunk {
+            // SAFETY: ✔️ all chunks reachable from `self.first` are live with initialized headers
+            let ChunkHeader { next, size } = unsafe { c.as_ptr().read() };
+            chunk = next;
+            // SAFETY: ✔️ `size` was already validated as a layout when `c` was allocated
+            let layout = unsafe { Layout::from_size_align_unchecked(size, align_of::<ChunkHeader>()) };
+            // SAFETY: ✔️ `c` was allocated by `self.backing` with `layout`
+            unsafe { self.backing.free(c.cast(), layout) };
+        }
+    }
+}
+
+
+
+// meta::*
+
+impl<Backing: fat::Alloc + fat::Free> Meta for &'_ Arena<Backing> {
+    type Error                  = ();
+    const MAX_ALIGN : Alignment = Alignment::MAX;
+    const MAX_SIZE  : usize     = usize::MAX;
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl<Backing: fat::Alloc + fat::Free> ZstSupported for &'_ Arena<Backing> {}
+
+// SAFETY: ✔️ ZSTs are dangling pointers that never touch `Backing`
+unsafe impl<Backing: fat::Alloc + fat::Free> ZstInfalliable for &'_ Arena<Backing> {}
+
+// SAFETY: ✔️ every non-zero-sized allocation lies within one of the chunks
+unsafe impl<Backing: fat::Alloc + fat::Free> Owns for Arena<Backing> {
+    fn owns(&self, ptr: AllocNN) -> bool {
+        let addr = ptr.as_ptr() as usize;
+        let mut chunk = self.first.get();
+        while let Some(c) = chunk {
+            // SAFETY: ✔️ all chunks reachable from `self.first` are live with initialized headers
+            let (next, size) = unsafe { ((*c.as_ptr()).next, (*c.as_ptr()).size) };
+            if addr.wrapping_sub(c.as_ptr() as usize) < size { return true }
+            chunk = next;
+        }
+        false
+    }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ if `alloc_uninit` succeeds, the allocation is `layout.size()` bytes within a chunk, aligned to `layout.align()`, and bumped past so it's never handed out again (until `reset`, which requires `&mut Arena`.)
+// SAFETY: ✔️ chunks are only freed on drop.  Ergo, we must implement this on a *reference* to Arena, not a value of it.
+//
+unsafe impl<Backing: fat::Alloc + fat::Free> fat::Alloc for &'_ Arena<Backing> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> {
+        if layout.size() == 0 {
+            #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+            return Ok(util::nn::dangling(layout));
+        }
+
+        let mut chunk = self.current.get();
+        while let Some(c) = chunk {
+            if let Some((alloc, used)) = Arena::<Backing>::try_alloc_in(c, self.used.get(), layout) {
+                self.used.set(used);
+                #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+                return Ok(alloc);
+            }
+            // Doesn't fit - abandon the rest of `c` and move on to the next (previously `reset`) chunk, if any
+            // SAFETY: ✔️ `c` is live with an initialized header
+            chunk = unsafe { (*c.as_ptr()).next };
+            if chunk.is_none() { break }
+            self.current.set(chunk);
+            self.used.set(size_of::<ChunkHeader>());
+        }
+
+        let c = self.grow(layout)?;
+        let (alloc, used) = Arena::<Backing>::try_alloc_in(c, size_of::<ChunkHeader>(), layout).ok_or(())?;
+        self.current.set(Some(c));
+        self.used.set(used);
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+        Ok(alloc)
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ `free` should be safe to call on any allocated `ptr` belonging to `self` - as a no-op, it trivially is
+//
+unsafe impl<Backing: fat::Alloc + fat::Free> fat::Free for &'_ Arena<Backing> {
+    unsafe fn free(&self, _ptr: AllocNN, _layout: Layout) {
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() - 1);
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ if `realloc_uninit` reallocates in place, `ptr` was the most recent allocation of the current chunk, is sufficiently aligned, and `new_layout.size()` bytes still fit within the chunk.
+//
+unsafe impl<Backing: fat::Alloc + fat::Free> fat::Realloc for &'_ Arena<Backing> {
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        if old_layout == new_layout { return Ok(ptr) }
+
+        // In-place fast path:  if `ptr` was the most recent allocation, and is already sufficiently aligned, just move the end of it.
+        // ZSTs are dangling, and thus never the most recent allocation.
+        if let Some(c) = self.current.get() {
+            let start = (ptr.as_ptr() as usize).wrapping_sub(c.as_ptr() as usize);
+            // SAFETY: ✔️ `c` is live with an initialized header
+            let size = unsafe { (*c.as_ptr()).size };
+            if old_layout.size() != 0 && start.wrapping_add(old_layout.size()) == self.used.get() && (ptr.as_ptr() as usize) % new_layout.align() == 0 {
+                if let Some(end) = start.checked_add(new_layout.size()).filter(|end| *end <= size) {
+                    self.used.set(end);
+                    return Ok(ptr);
+                }
+            }
+        }
+
+        // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        unsafe { fat::realloc_by_copy(self, self, ptr, old_layout, new_layout) }
+    }
+}
+
+#[cfg(allocator_api = "1.50")] #[no_implicit_prelude] mod cleanroom {
+    use crate::{impls, fat};
+    use super::Arena;
+
+    impls! {
+        unsafe impl['a, Backing: fat::Alloc + fat::Free] core::alloc::Allocator(unstable 1.50) for &'a Arena<Backing> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+/// Header at the start of every chunk
+#[repr(C)] struct ChunkHeader {
+    next:   Option<NonNull<ChunkHeader>>,
+    size:   usize, // of the entire chunk, including this header
+}
+
+
+
+#[cfg(all(test, feature = "alloc"))] mod backing_global {
+    use crate::allocator::alloc::Global;
+    use super::{fat, Arena};
+
+    #[test] fn test_quick() {
+        use crate::boxed::ABox;
+
+        let mut arena = Arena::<Global>::with_chunk_size_in(Global, 256);
+        for _ in 0 .. 3 {
+            let boxes = [(); 1024].map(|_| ABox::try_new_in(42u64, &arena).unwrap());
+            assert!(boxes.iter().all(|b| **b == 42));
+            let big = ABox::try_new_in([1u8; 4096], &arena).unwrap();
+            assert!(big.iter().all(|b| *b == 1));
+            drop((boxes, big));
+            assert!(arena.chunks() <= 8, "chunks should grow geometrically, and be reused after `reset`");
+            arena.reset();
+        }
+    }
+
+    #[test] fn realloc_in_place() {
+        use crate::vec::AVec;
+
+        let arena = Arena::<Global>::new();
+        let mut v = AVec::<u32, _>::new_in(&arena);
+        v.try_reserve(1).unwrap();
+        let ptr = v.as_ptr();
+        for i in 0 .. 512 { v.try_reserve(1).unwrap(); v.try_push(i).unwrap() }
+        assert_eq!(ptr, v.as_ptr(), "most recent allocation should grow in place");
+        assert_eq!(1, arena.chunks());
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(&Arena::<Global>::new()) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(&Arena::<Global>::new()) }
+    #[test] fn fat_uninit()             { unsafe { fat::test::uninit_alloc_unsound(&Arena::<Global>::new()) } }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(&Arena::<Global>::new()) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(&Arena::<Global>::new()) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(&Arena::<Global>::new()) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(&Arena::<Global>::new()) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use crate::allocator::c::Malloc;
+    use super::{fat, Arena};
+
+    #[test] fn fat_alignment()          { fat::test::alignment(&Arena::<Malloc>::new()) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(&Arena::<Malloc>::new()) }
+}
@@ -0,0 +1,256 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::fmt::{self, Debug, Formatter};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+
+
+/// Thread-safe, lock-free bump-allocate from a slice of memory.
+///
+/// Like [`Bump`](super::Bump), but the cursor is advanced with compare-and-swap, making this [`Sync`].
+/// This allows sharing it between worker threads, or using it as a `#[global_allocator]`:
+///
+/// ```
+/// # use ialloc::allocator::simple::AtomicBump;
+/// # use core::mem::MaybeUninit;
+/// static mut BUFFER : [MaybeUninit<u8>; 4096] = [MaybeUninit::uninit(); 4096];
+/// // #[global_allocator]
+/// static ALLOC : AtomicBump<'static> = unsafe { AtomicBump::from_raw_parts(core::ptr::addr_of!(BUFFER).cast_mut().cast(), 4096) };
+/// ```
+///
+/// Memory is only reclaimed in bulk, by [`reset`](Self::reset)ting, or by dropping the `AtomicBump`.
+/// The one exception:  reallocating the most recent allocation grows or shrinks it in place.
+pub struct AtomicBump<'a> {
+    base:       *mut MaybeUninit<u8>,
+    capacity:   usize,
+    used:       AtomicUsize,
+    #[cfg(debug_assertions)] outstanding_allocs: AtomicUsize,
+    phantom:    PhantomData<&'a [MaybeUninit<u8>]>, // `&'a mut` would be more accurate, but isn't allowed in `const fn`s at our MSRV
+}
+
+// SAFETY: ✔️ `AtomicBump` is semantically a `&'a mut [MaybeUninit<u8>]` (which is `Send`) handed out in disjoint pieces
+unsafe impl Send for AtomicBump<'_> {}
+
+// SAFETY: ✔️ all shared state is only mutated through atomics, and the ranges handed out by compare-and-swap are disjoint
+unsafe impl Sync for AtomicBump<'_> {}
+
+impl<'a> Debug for AtomicBump<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        #[cfg(    debug_assertions )] return write!(f, "AtomicBump {{ buffer: [...{} bytes], outstanding_allocs: {} }}", self.available(), self.outstanding_allocs.load(Relaxed));
+        #[cfg(not(debug_assertions))] return write!(f, "AtomicBump {{ buffer: [...{} bytes], outstanding_allocs: ?? }}", self.available());
+    }
+}
+
+impl<'a> AtomicBump<'a> {
+    pub fn from_array<const N: usize>(array: &'a mut MaybeUninit<[MaybeUninit<u8>; N]>) -> Self {
+        // SAFETY: ✔️ supposedly safe per <https://doc.rust-lang.org/core/mem/union.MaybeUninit.html#initializing-an-array-element-by-element>
+        let array : &mut [MaybeUninit<u8>; N] = unsafe { array.assume_init_mut() };
+        Self::new(&mut array[..])
+    }
+
+    pub fn new(buffer: &'a mut [MaybeUninit<u8>]) -> Self {
+        let len = buffer.len();
+        // SAFETY: ✔️ `buffer` is exclusively borrowed for `'a` and valid for `len` bytes
+        unsafe { Self::from_raw_parts(buffer.as_mut_ptr(), len) }
+    }
+
+    /// Bump-allocate from `len` bytes starting at `ptr`.
+    ///
+    /// Unlike [`new`](Self::new), this is `const`, allowing `AtomicBump` to be constructed in a `static` (e.g. over a `static mut` buffer.)
+    ///
+    /// ### Safety
+    /// *   `ptr` must be valid for reads and writes of `len` bytes for `'a`.
+    /// *   Nothing else may access the memory `ptr` points to for `'a`, except through allocations from this `AtomicBump`.
+    pub const unsafe fn from_raw_parts(ptr: *mut MaybeUninit<u8>, len: usize) -> Self {
+        Self {
+            base:       ptr,
+            capacity:   len,
+            used:       AtomicUsize::new(0),
+            #[cfg(debug_assertions)] outstanding_allocs: AtomicUsize::new(0),
+            phantom:    PhantomData,
+        }
+    }
+
+    /// Release all allocations, making the entire buffer available again.
+    ///
+    /// This takes `&mut self`, so no borrowing allocations (e.g. <code>[ABox](crate::boxed::ABox)&lt;T, &amp;AtomicBump&gt;</code>) can still be alive.
+    ///
+    /// ### Panics
+    /// *   (debug builds only) If allocations are still outstanding - e.g. they were [`forget`](core::mem::forget)ten.
+    pub fn reset(&mut self) {
+        #[cfg(debug_assertions)] assert_eq!(0, *self.outstanding_allocs.get_mut(), "allocator::simple::AtomicBump::reset: allocations are still outstanding");
+        *self.used.get_mut() = 0;
+    }
+
+    fn available(&self) -> usize { self.capacity - self.used.load(Relaxed) }
+}
+
+impl<'a> Drop for AtomicBump<'a> {
+    fn drop(&mut self) {
+        // As `'a` is likely nonstatic, this has a pretty serious chance of being a soundness bug - e.g. perhaps `ABox::into_inner` outlived `AtomicBump`.
+        #[cfg(debug_assertions)] assert_eq!(0, *self.outstanding_allocs.get_mut(), "allocator::simple::AtomicBump has outstanding allocations");
+    }
+}
+
+
+
+// meta::*
+
+impl<'a> Meta for AtomicBump<'a> {
+    type Error                  = ();
+    const MAX_ALIGN : Alignment = Alignment::MAX;
+    const MAX_SIZE  : usize     = usize::MAX;
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl ZstSupported for AtomicBump<'_> {}
+
+// SAFETY: ✔️ ZSTs are dangling pointers that never touch the buffer
+unsafe impl ZstInfalliable for AtomicBump<'_> {}
+
+// SAFETY: ✔️ every non-zero-sized allocation lies within the buffer
+unsafe impl Owns for AtomicBump<'_> {
+    fn owns(&self, ptr: AllocNN) -> bool { (ptr.as_ptr() as usize).wrapping_sub(self.base as usize) < self.capacity }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ if `alloc_uninit` succeeds, the allocation is `layout.size()` bytes within the buffer, aligned to `layout.align()`, and the CAS guarantees no other allocation overlaps it (until `reset`, which requires `&mut AtomicBump`.)
+//
+unsafe impl<'a> fat::Alloc for AtomicBump<'a> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<crate::AllocNN, Self::Error> {
+        let align = layout.align();
+        let size = layout.size();
+
+        if size == 0 {
+            #[cfg(debug_assertions)] self.outstanding_allocs.fetch_add(1, Relaxed);
+            return Ok(crate::util::nn::dangling(layout));
+        }
+
+        let align_mask = align.wrapping_sub(1);
+        let mut used = self.used.load(Relaxed);
+        let start = loop {
+            let misalign = align_mask & (self.base as usize).wrapping_add(used);
+            let skip = align.wrapping_sub(misalign) & align_mask;
+            let start = used.saturating_add(skip);
+            let end = start.saturating_add(size);
+            if end > self.capacity { return Err(()) } // ≈ OOM
+            // Relaxed is sufficient:  the CAS only has to hand out disjoint ranges - it doesn't publish the memory's contents.
+            match self.used.compare_exchange_weak(used, end, Relaxed, Relaxed) {
+                Ok(_)       => break start,
+                Err(actual) => used = actual,
+            }
+        };
+
+        #[cfg(debug_assertions)] self.outstanding_allocs.fetch_add(1, Relaxed);
+        // SAFETY: ✔️ `start < end <= capacity`, so this stays within the buffer
+        Ok(unsafe { NonNull::new_unchecked(self.base.add(start)) })
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ `free` should be safe to call on any allocated `ptr` belonging to `self` - as a no-op, it trivially is
+//
+unsafe impl<'a> fat::Free for AtomicBump<'a> {
+    unsafe fn free(&self, _ptr: crate::AllocNN, _layout: Layout) {
+        #[cfg(debug_assertions)] self.outstanding_allocs.fetch_sub(1, Relaxed);
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ if `realloc_uninit` reallocates in place, `ptr` was the most recent allocation when the CAS succeeded, is sufficiently aligned, and `new_layout.size()` bytes still fit within the buffer.
+//
+unsafe impl<'a> fat::Realloc for AtomicBump<'a> {
+    unsafe fn realloc_uninit(&self, ptr: crate::AllocNN, old_layout: Layout, new_layout: Layout) -> Result<crate::AllocNN, Self::Error> {
+        if old_layout == new_layout { return Ok(ptr) }
+
+        // In-place fast path:  if `ptr` was the most recent allocation, and is already sufficiently aligned, just move the end of it.
+        // ZSTs are dangling, and thus never the most recent allocation.
+        // If another thread allocates in the meantime, the CAS fails, and we fall back on allocating anew.
+        let start = (ptr.as_ptr() as usize).wrapping_sub(self.base as usize);
+        let old_end = start.wrapping_add(old_layout.size());
+        if old_layout.size() != 0 && old_end == self.used.load(Relaxed) && (ptr.as_ptr() as usize) % new_layout.align() == 0 {
+            let new_end = start.checked_add(new_layout.size()).filter(|end| *end <= self.capacity);
+            if let Some(new_end) = new_end {
+                if self.used.compare_exchange(old_end, new_end, Relaxed, Relaxed).is_ok() { return Ok(ptr) }
+            }
+        }
+
+        // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        unsafe { fat::realloc_by_copy(self, self, ptr, old_layout, new_layout) }
+    }
+}
+
+#[no_implicit_prelude] mod cleanroom {
+    use crate::impls;
+    use super::AtomicBump;
+
+    impls! {
+        unsafe impl     core::alloc::GlobalAlloc for     AtomicBump<'static> => ialloc::fat::Realloc;
+        unsafe impl['o] core::alloc::GlobalAlloc for &'o AtomicBump<'static> => core::ops::Deref;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl['a] core::alloc::Allocator(unstable 1.50) for AtomicBump<'a> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[test] fn test_static() {
+    use core::alloc::GlobalAlloc;
+
+    static mut BUFFER : [MaybeUninit<u8>; 4096] = [MaybeUninit::uninit(); 4096];
+    // SAFETY: ✔️ `BUFFER` is only ever accessed through `ALLOC`
+    static ALLOC : AtomicBump<'static> = unsafe { AtomicBump::from_raw_parts(core::ptr::addr_of!(BUFFER).cast_mut().cast(), 4096) };
+
+    let layout = Layout::new::<u64>();
+    // SAFETY: ✔️ `layout` is non-zero sized
+    let a = unsafe { ALLOC.alloc(layout) };
+    assert!(!a.is_null());
+    // SAFETY: ✔️ `a` was allocated by `ALLOC` with `layout`
+    let a2 = unsafe { ALLOC.realloc(a, layout, 64) };
+    assert_eq!(a, a2, "most recent allocation should grow in place");
+    // SAFETY: ✔️ `a2` was reallocated by `ALLOC` with a size of 64
+    unsafe { ALLOC.dealloc(a2, Layout::from_size_align(64, 8).unwrap()) };
+}
+
+#[test] fn test_threads() {
+    use crate::boxed::ABox;
+
+    let mut buffer = MaybeUninit::new([(); 65536].map(|_| MaybeUninit::<u8>::new(0xFF)));
+    let mut alloc = AtomicBump::from_array(&mut buffer);
+    for _ in 0 .. 3 {
+        std::thread::scope(|s| {
+            let alloc = &alloc;
+            let threads = [(); 4].map(|_| s.spawn(move || {
+                let mut n = 0_u32;
+                while let Ok(b) = ABox::try_new_in([n; 4], alloc) {
+                    core::mem::forget(b); // keep it allocated
+                    n += 1;
+                }
+                n
+            }));
+            let total : u32 = threads.into_iter().map(|t| t.join().unwrap()).sum();
+            assert!(total >= 65536 / 16 - 1, "every 16 byte block should've been handed out (except perhaps one lost to initial alignment)");
+        });
+        #[cfg(debug_assertions)] { *alloc.outstanding_allocs.get_mut() = 0; } // we forgot every allocation above
+        alloc.reset();
+    }
+}
+
+#[test] fn fat_alignment()          { fat::test::alignment(AtomicBump::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(AtomicBump::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_uninit()             { unsafe { fat::test::uninit_alloc_unsound(AtomicBump::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) } }
+#[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(AtomicBump::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zeroed()             { fat::test::zeroed_alloc(AtomicBump::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(AtomicBump::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(AtomicBump::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
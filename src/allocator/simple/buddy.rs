@@ -0,0 +1,462 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::fmt::{self, Debug, Formatter};
+use core::marker::PhantomData;
+use core::mem::{size_of, MaybeUninit};
+use core::ptr::NonNull;
+
+
+
+/// Binary buddy allocate from a slice of memory.
+///
+/// Like [`Bump`](super::Bump), this manages a caller-provided region, but can actually free (and reuse) memory.
+/// Allocations are rounded up to a power of two blocks (at least [`MIN_BLOCK`](Self::MIN_BLOCK) bytes.)
+/// Larger blocks are split in half as necessary to satisfy smaller allocations, and adjacent "buddies" are coalesced again when freed.
+/// Reallocating shrinks in place, and grows in place if the following buddies are free.
+///
+/// ## Region Layout
+///
+/// The start of the region is aligned to 4 KiB (the maximum supported alignment), and the end of the region stores a bitmap tracking which blocks are free -
+/// about 2 bits per [`MIN_BLOCK`](Self::MIN_BLOCK) bytes of the region (≈ 1.6% overhead on 64-bit.)
+///
+/// ## Allocation Scaling Behavior
+///
+/// Allocating, freeing, and reallocating are all `O(log n)` in the size of the region.
+/// Construction is `O(n)` (the bitmap is zeroed.)
+pub struct Buddy<'a> {
+    base:           NonNull<MaybeUninit<u8>>,
+    blocks:         usize, // of `MIN_BLOCK` bytes each
+    orders:         usize, // blocks of size `MIN_BLOCK << order` exist for `order in 0 .. orders`
+    bitmap:         NonNull<Cell<usize>>,
+    bitmap_offsets: [usize; ORDERS], // word offset of each order's bitmap, which has 1 bit per block of that order
+    free:           [Cell<Option<NonNull<FreeBlock>>>; ORDERS],
+    #[cfg(debug_assertions)] outstanding_allocs: Cell<usize>,
+    phantom:        PhantomData<&'a mut [MaybeUninit<u8>]>,
+}
+
+// SAFETY: ✔️ `Buddy` is semantically a `Cell<&'a mut [MaybeUninit<u8>]>` plus some bookkeeping, which is `Send`
+unsafe impl Send for Buddy<'_> {}
+
+impl<'a> Debug for Buddy<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        #[cfg(    debug_assertions )] return write!(f, "Buddy {{ buffer: [...{} bytes], outstanding_allocs: {} }}", self.blocks * MIN_BLOCK, self.outstanding_allocs.get());
+        #[cfg(not(debug_assertions))] return write!(f, "Buddy {{ buffer: [...{} bytes], outstanding_allocs: ?? }}", self.blocks * MIN_BLOCK);
+    }
+}
+
+impl<'a> Buddy<'a> {
+    /// The smallest block size, in bytes.  All allocations are rounded up to at least this.
+    pub const MIN_BLOCK : usize = MIN_BLOCK;
+
+    pub fn from_array<const N: usize>(array: &'a mut MaybeUninit<[MaybeUninit<u8>; N]>) -> Self {
+        // SAFETY: ✔️ supposedly safe per <https://doc.rust-lang.org/core/mem/union.MaybeUninit.html#initializing-an-array-element-by-element>
+        let array : &mut [MaybeUninit<u8>; N] = unsafe { array.assume_init_mut() };
+        Self::new(&mut array[..])
+    }
+
+    pub fn new(buffer: &'a mut [MaybeUninit<u8>]) -> Self {
+        let start = buffer.as_mut_ptr();
+        let skip = start.align_offset(MAX_ALIGN).min(buffer.len());
+        let len = buffer.len() - skip;
+
+        // Find the largest number of blocks that fits in `len` along with it's bitmap.
+        // The bitmap needs < 2 bits (¼ byte) per block across all orders, plus at most 1 word of rounding per order:
+        // that worst case gives a lower bound within `3 + ORDERS * size_of::<usize>() / MIN_BLOCK` blocks of the answer.
+        let mut blocks = len.saturating_sub(ORDERS * size_of::<usize>()) / (4 * MIN_BLOCK + 1) * 4;
+        while blocks < len / MIN_BLOCK && Self::region_size(blocks + 1) <= len { blocks += 1 }
+        let orders = (usize::BITS - blocks.leading_zeros()) as usize; // 0 blocks ⮕ 0 orders, 1 block ⮕ 1 order, 2..=3 blocks ⮕ 2 orders, ...
+
+        let mut bitmap_offsets = [0; ORDERS];
+        let mut words = 0;
+        for (order, offset) in bitmap_offsets.iter_mut().enumerate().take(orders) {
+            *offset = words;
+            words += Self::words_for(blocks >> order);
+        }
+
+        // SAFETY: ✔️ `skip <= buffer.len()`
+        let base = unsafe { start.add(skip) };
+        // SAFETY: ✔️ `blocks * MIN_BLOCK + words * size_of::<usize>() <= len` per the search above.  `base` is `MAX_ALIGN`ed, and `MIN_BLOCK` is a multiple of `align_of::<usize>()`, so this is aligned.
+        let bitmap = unsafe { base.add(blocks * MIN_BLOCK) }.cast::<Cell<usize>>();
+        for word in 0 .. words {
+            // SAFETY: ✔️ `word` is in bounds of the bitmap, per above
+            unsafe { bitmap.add(word).write(Cell::new(0)) };
+        }
+
+        let buddy = Self {
+            // SAFETY: ✔️ derived from a slice pointer, which is never null
+            base:   unsafe { NonNull::new_unchecked(base) },
+            blocks,
+            orders,
+            // SAFETY: ✔️ derived from a slice pointer, which is never null
+            bitmap: unsafe { NonNull::new_unchecked(bitmap) },
+            bitmap_offsets,
+            free:   [(); ORDERS].map(|_| Cell::new(None)),
+            #[cfg(debug_assertions)] outstanding_allocs: Cell::new(0),
+            phantom: PhantomData,
+        };
+
+        // Carve the region into the largest possible blocks (one per set bit of `blocks`), each aligned to it's own size
+        let mut offset = 0;
+        for order in (0 .. orders).rev() {
+            if blocks & (1 << order) != 0 {
+                buddy.push(order, offset);
+                offset += MIN_BLOCK << order;
+            }
+        }
+        buddy
+    }
+
+    /// Total bytes of blocks and bitmap required for `blocks` blocks.
+    fn region_size(blocks: usize) -> usize { blocks * MIN_BLOCK + Self::bitmap_words(blocks) * size_of::<usize>() }
+
+    /// Total `usize`s of bitmap required for `blocks` blocks.
+    fn bitmap_words(blocks: usize) -> usize { (0 .. ORDERS).map(|order| Self::words_for(blocks >> order)).sum() }
+
+    fn words_for(bits: usize) -> usize { bits.div_ceil(usize::BITS as usize) }
+
+    /// The order of block required for `layout`, or [`None`] if it's too large for this allocator.
+    fn order_of(&self, layout: Layout) -> Option<usize> {
+        if layout.align() > MAX_ALIGN { return None }
+        let size = layout.size().max(layout.align()).max(MIN_BLOCK).checked_next_power_of_two()?;
+        let order = (size / MIN_BLOCK).trailing_zeros() as usize;
+        if order >= self.orders { return None }
+        Some(order)
+    }
+
+    /// The order of block `layout` was allocated with, reporting a bug if `layout` couldn't have been allocated by this allocator.
+    #[track_caller] fn order_of_allocated(&self, layout: Layout) -> usize {
+        match self.order_of(layout) {
+            Some(order) => order,
+            None if layout.align() > MAX_ALIGN => bug::ub::invalid_free_align_for_allocator(layout.align()),
+            None => bug::ub::invalid_free_size_for_allocator(layout.size()),
+        }
+    }
+
+    fn bit(&self, order: usize, offset: usize) -> (&Cell<usize>, usize) {
+        let index = offset / (MIN_BLOCK << order);
+        debug_assert!(index < self.blocks >> order);
+        let word = self.bitmap_offsets[order] + index / usize::BITS as usize;
+        // SAFETY: ✔️ `word` is in bounds of the bitmap, which was initialized in `new`
+        (unsafe { &*self.bitmap.as_ptr().add(word) }, 1 << (index % usize::BITS as usize))
+    }
+
+    /// Is the block of `order` at `offset` (which might not exist) free?
+    fn is_free(&self, order: usize, offset: usize) -> bool {
+        if order >= self.orders || offset / (MIN_BLOCK << order) >= self.blocks >> order { return false }
+        let (word, mask) = self.bit(order, offset);
+        word.get() & mask != 0
+    }
+
+    fn block(&self, offset: usize) -> NonNull<FreeBlock> {
+        // SAFETY: ✔️ `offset` is within the region
+        unsafe { NonNull::new_unchecked(self.base.as_ptr().add(offset).cast()) }
+    }
+
+    /// Mark the block of `order` at `offset` as free, and add it to the free list.
+    fn push(&self, order: usize, offset: usize) {
+        let block = self.block(offset);
+        let next = self.free[order].get();
+        // SAFETY: ✔️ `block` is unallocated, and `FreeBlock` fits in (and is aligned by) every block
+        unsafe { block.as_ptr().write(FreeBlock { prev: None, next }) };
+        // SAFETY: ✔️ `next` is on the free list
+        if let Some(next) = next { unsafe { (*next.as_ptr()).prev = Some(block) } }
+        self.free[order].set(Some(block));
+        let (word, mask) = self.bit(order, offset);
+        word.set(word.get() | mask);
+    }
+
+    /// Mark the (free) block of `order` at `offset` as allocated, and remove it from the free list.
+    fn remove(&self, order: usize, offset: usize) {
+        let block = self.block(offset);
+        // SAFETY: ✔️ `block` is on the free list
+        let FreeBlock { prev, next } = unsafe { block.as_ptr().read() };
+        match prev {
+            // SAFETY: ✔️ `prev` is on the free list
+            Some(prev)  => unsafe { (*prev.as_ptr()).next = next },
+            None        => self.free[order].set(next),
+        }
+        // SAFETY: ✔️ `next` is on the free list
+        if let Some(next) = next { unsafe { (*next.as_ptr()).prev = prev } }
+        let (word, mask) = self.bit(order, offset);
+        word.set(word.get() & !mask);
+    }
+
+    fn offset_of(&self, ptr: AllocNN) -> usize { (ptr.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize) }
+
+    /// Validate `ptr` (debug only), and get it's offset.
+    fn offset_of_allocated(&self, ptr: AllocNN, order: usize) -> usize {
+        let offset = self.offset_of(ptr);
+        if cfg!(debug_assertions) {
+            if order >= self.orders || offset % (MIN_BLOCK << order) != 0 || offset >= self.blocks * MIN_BLOCK { bug::ub::invalid_ptr_for_allocator(ptr) }
+            if self.is_free(order, offset) { bug::ub::freed_ptr_for_allocator(ptr) }
+        }
+        offset
+    }
+}
+
+
+
+// meta::*
+
+impl<'a> Meta for Buddy<'a> {
+    type Error                  = ();
+    const MAX_ALIGN : Alignment = Alignment::constant(MAX_ALIGN);
+    const MAX_SIZE  : usize     = usize::MAX;
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl ZstSupported for Buddy<'_> {}
+
+// SAFETY: ✔️ ZSTs are dangling pointers that never touch the region
+unsafe impl ZstInfalliable for Buddy<'_> {}
+
+// SAFETY: ✔️ every non-zero-sized allocation lies within the buddy blocks
+unsafe impl Owns for Buddy<'_> {
+    fn owns(&self, ptr: AllocNN) -> bool { (ptr.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize) < self.blocks * MIN_BLOCK }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other:  `order_of(layout)` consistently determines the size of block used for `layout`.
+// SAFETY: ✔️ if `alloc_uninit` succeeds, the allocation is a block of `MIN_BLOCK << order` ≥ `layout.size()` bytes, removed from the free lists.
+//  Blocks are aligned to `min(their size, MAX_ALIGN)` ≥ `layout.align()` as the region starts `MAX_ALIGN`ed and every block's offset is a multiple of it's size.
+//
+unsafe impl<'a> fat::Alloc for Buddy<'a> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> {
+        if layout.size() == 0 {
+            if layout.align() > MAX_ALIGN { return Err(()) }
+            #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+            return Ok(util::nn::dangling(layout));
+        }
+
+        let order = self.order_of(layout).ok_or(())?;
+        let mut available = order;
+        let block = loop {
+            if available >= self.orders { return Err(()) } // ≈ OOM
+            if let Some(block) = self.free[available].get() { break block }
+            available += 1;
+        };
+
+        let offset = self.offset_of(block.cast());
+        self.remove(available, offset);
+        while available > order {
+            available -= 1;
+            self.push(available, offset + (MIN_BLOCK << available));
+        }
+
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+        Ok(block.cast())
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ `free` should be safe to call on any allocated `ptr` belonging to `self`
+//
+unsafe impl<'a> fat::Free for Buddy<'a> {
+    unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() - 1);
+        if layout.size() == 0 { return }
+
+        let mut order = self.order_of_allocated(layout);
+        let mut offset = self.offset_of_allocated(ptr, order);
+
+        // Coalesce with free buddies
+        loop {
+            let buddy = offset ^ (MIN_BLOCK << order);
+            if !self.is_free(order, buddy) { break }
+            self.remove(order, buddy);
+            offset = offset.min(buddy);
+            order += 1;
+        }
+        self.push(order, offset);
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ if `realloc_uninit` reallocates in place:
+//  • When shrinking, the trailing halves are split off and freed, and the remaining block is still ≥ `new_layout.size()` bytes.
+//  • When growing, every buddy between the old and new block size was free (and is now removed from the free lists.)
+//  • In both cases, `ptr` is checked to be aligned to `new_layout.align()`.
+//
+unsafe impl<'a> fat::Realloc for Buddy<'a> {
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        if old_layout == new_layout { return Ok(ptr) }
+
+        if old_layout.size() != 0 && new_layout.size() != 0 && (ptr.as_ptr() as usize) % new_layout.align() == 0 {
+            let old_order = self.order_of_allocated(old_layout);
+            let offset = self.offset_of_allocated(ptr, old_order);
+            let new_order = self.order_of(new_layout).ok_or(())?;
+
+            if new_order <= old_order {
+                for order in (new_order .. old_order).rev() { self.push(order, offset + (MIN_BLOCK << order)) }
+                return Ok(ptr);
+            }
+
+            // Grow in place if `ptr` is the first half of every larger block, and all the second halves are free
+            let can_grow = offset % (MIN_BLOCK << new_order) == 0 && (old_order .. new_order).all(|order| self.is_free(order, offset + (MIN_BLOCK << order)));
+            if can_grow {
+                for order in old_order .. new_order { self.remove(order, offset + (MIN_BLOCK << order)) }
+                return Ok(ptr);
+            }
+        }
+
+        // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        unsafe { fat::realloc_by_copy(self, self, ptr, old_layout, new_layout) }
+    }
+}
+
+impl<'a> Drop for Buddy<'a> {
+    fn drop(&mut self) {
+        // As `'a` is likely nonstatic, this has a pretty serious chance of being a soundness bug - e.g. perhaps `ABox::into_inner` outlived `Buddy`.
+        #[cfg(debug_assertions)] assert_eq!(0, self.outstanding_allocs.get(), "allocator::simple::Buddy has outstanding allocations");
+    }
+}
+
+#[no_implicit_prelude] mod cleanroom {
+    use crate::impls;
+    use super::Buddy;
+
+    impls! {
+        unsafe impl     core::alloc::GlobalAlloc for     Buddy<'static> => ialloc::fat::Realloc;
+        unsafe impl['o] core::alloc::GlobalAlloc for &'o Buddy<'static> => core::ops::Deref;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl['a] core::alloc::Allocator(unstable 1.50) for Buddy<'a> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+const MAX_ALIGN : usize = 4096;
+const MIN_BLOCK : usize = size_of::<FreeBlock>();
+const ORDERS    : usize = usize::BITS as usize;
+const _ : () = assert!(MIN_BLOCK.is_power_of_two());
+
+/// Stored in every free block, doubly linking the free list of it's order (so any block can be removed when coalescing.)
+struct FreeBlock {
+    prev: Option<NonNull<FreeBlock>>,
+    next: Option<NonNull<FreeBlock>>,
+}
+
+
+
+#[test] fn test_quick() {
+    use crate::boxed::ABox;
+
+    let mut buffer = MaybeUninit::new([(); 65536].map(|_| MaybeUninit::<u8>::new(0xFF)));
+    let alloc = Buddy::from_array(&mut buffer);
+    for _ in 0 .. 10 {
+        let integers = [(); 256].map(|_| ABox::try_new_in(42u32, &alloc).unwrap());
+        let large = ABox::try_new_in([1u8; 16384], &alloc).unwrap();
+        assert!(integers.iter().all(|i| **i == 42));
+        assert!(large.iter().all(|b| *b == 1));
+    }
+}
+
+#[test] fn coalesce() {
+    use crate::fat::*;
+
+    let mut buffer = MaybeUninit::new([(); 65536].map(|_| MaybeUninit::<u8>::new(0xFF)));
+    let alloc = Buddy::from_array(&mut buffer);
+    let largest = Layout::from_size_align(32768, 1).unwrap();
+    let small = Layout::from_size_align(MIN_BLOCK, 1).unwrap();
+
+    let a = alloc.alloc_uninit(largest).unwrap();
+    assert!(alloc.alloc_uninit(largest).is_err(), "the bitmap should prevent a second 32 KiB block from fitting in 64 KiB");
+    // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `largest`
+    unsafe { alloc.free(a, largest) };
+
+    let smalls = [(); 32768 / MIN_BLOCK].map(|_| alloc.alloc_uninit(small).unwrap());
+    for s in smalls.iter().copied() {
+        // SAFETY: ✔️ `s` belongs to `alloc` and was allocated with `small`
+        unsafe { alloc.free(s, small) };
+    }
+
+    let b = alloc.alloc_uninit(largest).expect("freed small blocks should've coalesced back into a 32 KiB block");
+    assert_eq!(a, b);
+    // SAFETY: ✔️ `b` belongs to `alloc` and was allocated with `largest`
+    unsafe { alloc.free(b, largest) };
+}
+
+#[test] fn realloc_in_place() {
+    use crate::fat::*;
+
+    let mut buffer = MaybeUninit::new([(); 65536].map(|_| MaybeUninit::<u8>::new(0xFF)));
+    let alloc = Buddy::from_array(&mut buffer);
+    let l16  = Layout::from_size_align( 16, 1).unwrap();
+    let l256 = Layout::from_size_align(256, 1).unwrap();
+    let l512 = Layout::from_size_align(512, 1).unwrap();
+    let small = Layout::from_size_align(MIN_BLOCK, 1).unwrap();
+
+    let a = alloc.alloc_uninit(l256).unwrap();
+    // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `l256`
+    let a2 = unsafe { alloc.realloc_uninit(a, l256, l16) }.unwrap();
+    assert_eq!(a, a2, "shrinking should always be in place");
+    // SAFETY: ✔️ `a2` belongs to `alloc` and was reallocated with `l16`
+    let a3 = unsafe { alloc.realloc_uninit(a2, l16, l256) }.unwrap();
+    assert_eq!(a, a3, "growing should be in place when all buddies are free (as shrinking just freed them)");
+
+    let mut rest = std::vec::Vec::new();
+    while let Ok(s) = alloc.alloc_uninit(small) { rest.push(s) }
+    // SAFETY: ✔️ `a3` belongs to `alloc` and was reallocated with `l256`
+    assert!(unsafe { alloc.realloc_uninit(a3, l256, l512) }.is_err(), "growing in place over allocated buddies would be unsound, and there's no room to move");
+
+    for s in rest {
+        // SAFETY: ✔️ `s` belongs to `alloc` and was allocated with `small`
+        unsafe { alloc.free(s, small) };
+    }
+    // SAFETY: ✔️ `a3` belongs to `alloc` and was reallocated with `l256` (the failed realloc left it untouched)
+    let a4 = unsafe { alloc.realloc_uninit(a3, l256, l512) }.unwrap();
+    // SAFETY: ✔️ `a4` belongs to `alloc` and was reallocated with `l512`
+    unsafe { alloc.free(a4, l512) };
+}
+
+#[test] fn new_maximizes_blocks() {
+    let mut buffer = MaybeUninit::new([(); 65536].map(|_| MaybeUninit::<u8>::new(0xFF)));
+    // SAFETY: ✔️ supposedly safe per <https://doc.rust-lang.org/core/mem/union.MaybeUninit.html#initializing-an-array-element-by-element>
+    let buffer : &mut [MaybeUninit<u8>; 65536] = unsafe { buffer.assume_init_mut() };
+    let skip = buffer.as_ptr().align_offset(MAX_ALIGN);
+    for len in (0 .. 65536 - skip).step_by(97).chain([65536 - skip]) {
+        let blocks = Buddy::new(&mut buffer[skip .. skip + len]).blocks;
+        assert!(Buddy::region_size(blocks) <= len, "{blocks} blocks don't fit in {len} bytes");
+        assert!(Buddy::region_size(blocks + 1) > len, "{} blocks would've fit in {len} bytes, but only {blocks} were used", blocks + 1);
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test] #[should_panic = "bug: undefined behavior"] fn double_free() {
+    use crate::fat::*;
+
+    let mut buffer = MaybeUninit::new([(); 65536].map(|_| MaybeUninit::<u8>::new(0xFF)));
+    let alloc = core::mem::ManuallyDrop::new(Buddy::from_array(&mut buffer)); // don't double panic in `Drop`
+    let layout = Layout::new::<[u8; 64]>();
+    let a = alloc.alloc_uninit(layout).unwrap();
+    let _b = alloc.alloc_uninit(layout).unwrap(); // prevent `a` from coalescing into a larger block
+    // SAFETY: ❌ intentionally double freeing to test bug detection
+    unsafe { alloc.free(a, layout); alloc.free(a, layout) };
+}
+
+#[test] #[should_panic = "larger than this allocator could have allocated"] fn free_oversized() {
+    use crate::fat::*;
+
+    let mut buffer = MaybeUninit::new([(); 65536].map(|_| MaybeUninit::<u8>::new(0xFF)));
+    let alloc = core::mem::ManuallyDrop::new(Buddy::from_array(&mut buffer)); // don't double panic in `Drop`
+    let layout = Layout::new::<[u8; 64]>();
+    let a = alloc.alloc_uninit(layout).unwrap();
+    // SAFETY: ❌ intentionally freeing with the wrong layout to test bug detection
+    unsafe { alloc.free(a, Layout::new::<[u8; 65536]>()) };
+}
+
+#[test] fn fat_alignment()          { fat::test::alignment(Buddy::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(Buddy::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+// fat::test::uninit_alloc_unsound omitted:  freeing writes (often zeroed) free list links into the start of the block, and reallocating the same block is the common case
+#[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(Buddy::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zeroed()             { fat::test::zeroed_alloc(Buddy::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(Buddy::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(Buddy::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
@@ -4,17 +4,34 @@ use crate::meta::*;
 use core::alloc::Layout;
 use core::cell::Cell;
 use core::fmt::{self, Debug, Formatter};
+use core::marker::PhantomData;
 use core::mem::MaybeUninit;
 use core::ptr::NonNull;
 
 
 
 /// Bump-allocate from a slice of memory.
+///
+/// Memory is only reclaimed in bulk, by [`rewind`](Self::rewind)ing to a [`checkpoint`](Self::checkpoint) (or via [`scope`](Self::scope)), or by dropping the `Bump`.
+/// The one exception:  reallocating the most recent allocation grows or shrinks it in place.
 pub struct Bump<'a> {
-    buffer: Cell<&'a mut [MaybeUninit<u8>]>,
+    base:       NonNull<MaybeUninit<u8>>,
+    capacity:   usize,
+    used:       Cell<usize>,
     #[cfg(debug_assertions)] outstanding_allocs: Cell<usize>,
+    phantom:    PhantomData<&'a mut [MaybeUninit<u8>]>,
 }
 
+/// A saved [`Bump`] state, to later [`rewind`](Bump::rewind) back to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BumpCheckpoint {
+    used: usize,
+    #[cfg(debug_assertions)] outstanding_allocs: usize,
+}
+
+// SAFETY: ✔️ `Bump` is semantically a `Cell<&'a mut [MaybeUninit<u8>]>`, which is `Send`
+unsafe impl Send for Bump<'_> {}
+
 impl<'a> Debug for Bump<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         #[cfg(    debug_assertions )] return write!(f, "Bump {{ buffer: [...{} bytes], outstanding_allocs: {} }}", self.available(), self.outstanding_allocs.get());
@@ -31,17 +48,48 @@ impl<'a> Bump<'a> {
 
     pub fn new(buffer: &'a mut [MaybeUninit<u8>]) -> Self {
         Self {
-            buffer: Cell::new(buffer),
+            capacity:   buffer.len(),
+            base:       NonNull::from(buffer).cast(),
+            used:       Cell::new(0),
             #[cfg(debug_assertions)] outstanding_allocs: Cell::new(0),
+            phantom:    PhantomData,
+        }
+    }
+
+    /// Save the current state of the allocator, to [`rewind`](Self::rewind) back to later.
+    pub fn checkpoint(&self) -> BumpCheckpoint {
+        BumpCheckpoint {
+            used: self.used.get(),
+            #[cfg(debug_assertions)] outstanding_allocs: self.outstanding_allocs.get(),
         }
     }
 
-    fn available(&self) -> usize {
-        let b = self.buffer.take();
-        let len = b.len();
-        self.buffer.set(b);
-        len
+    /// Release all memory allocated since `checkpoint` was taken, making it available for reuse.
+    ///
+    /// This takes `&mut self`, so no borrowing allocations (e.g. <code>[ABox](crate::boxed::ABox)&lt;T, &amp;Bump&gt;</code>) can still be alive.
+    ///
+    /// ### Panics
+    /// *   If `checkpoint` is newer than the allocator's current state (e.g. it was already rewound past, or `checkpoint` came from another `Bump`.)
+    /// *   (debug builds only) If allocations made since `checkpoint` are still outstanding - e.g. they were [`forget`](core::mem::forget)ten.
+    pub fn rewind(&mut self, checkpoint: BumpCheckpoint) {
+        assert!(checkpoint.used <= self.used.get(), "allocator::simple::Bump::rewind: checkpoint is newer than the allocator's current state");
+        // Allocations made before `checkpoint` but freed after it may mask allocations made after `checkpoint` that were never freed - this check is best effort.
+        #[cfg(debug_assertions)] assert!(self.outstanding_allocs.get() <= checkpoint.outstanding_allocs, "allocator::simple::Bump::rewind: allocations made since the checkpoint are still outstanding");
+        self.used.set(checkpoint.used);
+    }
+
+    /// Run `f`, then [`rewind`](Self::rewind) to release any memory it allocated.
+    ///
+    /// ### Panics
+    /// *   (debug builds only) If allocations made by `f` are still outstanding once it returns.
+    pub fn scope<R>(&mut self, f: impl FnOnce(&Self) -> R) -> R {
+        let checkpoint = self.checkpoint();
+        let r = f(self);
+        self.rewind(checkpoint);
+        r
     }
+
+    fn available(&self) -> usize { self.capacity - self.used.get() }
 }
 
 impl<'a> Drop for Bump<'a> {
@@ -67,6 +115,11 @@ impl ZstSupported for Bump<'_> {}
 
 unsafe impl ZstInfalliable for Bump<'_> {}
 
+// SAFETY: ✔️ every non-zero-sized allocation lies within the buffer
+unsafe impl Owns for Bump<'_> {
+    fn owns(&self, ptr: AllocNN) -> bool { (ptr.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize) < self.capacity }
+}
+
 
 
 // fat::*
@@ -82,32 +135,18 @@ unsafe impl<'a> fat::Alloc for Bump<'a> {
             return Ok(crate::util::nn::dangling(layout));
         }
 
-        let buffer = self.buffer.take();
+        let used = self.used.get();
         let align_mask = align.wrapping_sub(1);
-        let misalign = align_mask & (buffer.as_ptr() as usize);
-        if misalign == 0 {
-            if buffer.len() < size { // ≈ OOM
-                self.buffer.set(buffer);
-                Err(())
-            } else {
-                let (alloc, unalloc) = buffer.split_at_mut(size);
-
-                #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
-                self.buffer.set(unalloc);
-                Ok(unsafe { NonNull::new_unchecked(alloc.as_mut_ptr()) })
-            }
-        } else {
-            let skip = align - misalign;
-            if skip.saturating_add(size) > buffer.len() {
-                self.buffer.set(buffer);
-                Err(())
-            } else {
-                let (alloc, unalloc) = buffer[skip..].split_at_mut(size);
-                #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
-                self.buffer.set(unalloc);
-                Ok(unsafe { NonNull::new_unchecked(alloc.as_mut_ptr()) })
-            }
-        }
+        let misalign = align_mask & (self.base.as_ptr() as usize).wrapping_add(used);
+        let skip = align.wrapping_sub(misalign) & align_mask;
+        let start = used.saturating_add(skip);
+        let end = start.saturating_add(size);
+        if end > self.capacity { return Err(()) } // ≈ OOM
+
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+        self.used.set(end);
+        // SAFETY: ✔️ `start < end <= capacity`, so this stays within `buffer`
+        Ok(unsafe { NonNull::new_unchecked(self.base.as_ptr().add(start)) })
     }
 }
 
@@ -118,7 +157,23 @@ unsafe impl<'a> fat::Free for Bump<'a> {
 }
 
 unsafe impl<'a> fat::Realloc for Bump<'a> {
-    // TODO: if an allocation was the last allocation, add a in-place fast path?
+    unsafe fn realloc_uninit(&self, ptr: crate::AllocNN, old_layout: Layout, new_layout: Layout) -> Result<crate::AllocNN, Self::Error> {
+        if old_layout == new_layout { return Ok(ptr) }
+
+        // In-place fast path:  if `ptr` was the most recent allocation, and is already sufficiently aligned, just move the end of it.
+        // ZSTs are dangling, and thus never the most recent allocation.
+        let start = (ptr.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize);
+        if old_layout.size() != 0 && start.wrapping_add(old_layout.size()) == self.used.get() && (ptr.as_ptr() as usize) % new_layout.align() == 0 {
+            // Growing in place requires strictly less memory than allocating anew past the end of `ptr`, so there's no point falling back on failure.
+            let end = start.checked_add(new_layout.size()).ok_or(())?;
+            if end > self.capacity { return Err(()) } // ≈ OOM
+            self.used.set(end);
+            return Ok(ptr);
+        }
+
+        // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        unsafe { fat::realloc_by_copy(self, self, ptr, old_layout, new_layout) }
+    }
 }
 
 #[no_implicit_prelude] mod cleanroom {
@@ -176,3 +231,87 @@ unsafe impl<'a> fat::Realloc for Bump<'a> {
 #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(&Bump::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
 #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(&Bump::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
 #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(&Bump::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+
+
+
+#[test] fn realloc_in_place() {
+    use crate::fat::*;
+
+    let mut buffer : MaybeUninit<[_; 4096]> = MaybeUninit::uninit();
+    let alloc = Bump::from_array(&mut buffer);
+    let l16  = Layout::from_size_align(16, 4).unwrap();
+    let l64  = Layout::from_size_align(64, 4).unwrap();
+    let l8   = Layout::from_size_align( 8, 4).unwrap();
+
+    let a = alloc.alloc_uninit(l16).unwrap();
+    // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `l16`
+    let a2 = unsafe { alloc.realloc_uninit(a, l16, l64) }.unwrap();
+    assert_eq!(a, a2, "most recent allocation should grow in place");
+    assert_eq!(4096 - 64, alloc.available());
+
+    // SAFETY: ✔️ `a2` belongs to `alloc` and was reallocated with `l64`
+    let a3 = unsafe { alloc.realloc_uninit(a2, l64, l8) }.unwrap();
+    assert_eq!(a, a3, "most recent allocation should shrink in place");
+    assert_eq!(4096 - 8, alloc.available());
+
+    let b = alloc.alloc_uninit(l8).unwrap();
+    // SAFETY: ✔️ `a3` belongs to `alloc` and was reallocated with `l8`
+    let a4 = unsafe { alloc.realloc_uninit(a3, l8, l16) }.unwrap();
+    assert_ne!(a, a4, "no longer the most recent allocation - should've moved");
+
+    // SAFETY: ✔️ `a4` belongs to `alloc` and was reallocated with `l16` (and remains valid if reallocation fails)
+    assert!(unsafe { alloc.realloc_uninit(a4, l16, Layout::from_size_align(4096, 4).unwrap()) }.is_err());
+
+    // SAFETY: ✔️ `a4` and `b` belong to `alloc` and were (re)allocated with `l16` and `l8` respectively
+    unsafe { alloc.free(a4, l16) };
+    unsafe { alloc.free(b, l8) };
+}
+
+#[test] fn checkpoint_rewind() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 64]> = MaybeUninit::uninit();
+    let mut alloc = Bump::from_array(&mut buffer);
+
+    let a = ABox::try_new_in([1u8; 16], &alloc).unwrap();
+    let a_ptr = &*a as *const [u8; 16];
+    drop(a);
+    let checkpoint = alloc.checkpoint();
+
+    let b = ABox::try_new_in([2u8; 32], &alloc).unwrap();
+    let b_ptr = &*b as *const [u8; 32];
+    assert!(ABox::try_new_in([3u8; 32], &alloc).is_err());
+    drop(b);
+
+    alloc.rewind(checkpoint);
+    let c = ABox::try_new_in([4u8; 32], &alloc).unwrap();
+    assert_eq!(b_ptr, &*c as *const [u8; 32], "rewound memory should be reused");
+    assert_ne!(a_ptr as *const u8, &*c as *const [u8; 32] as *const u8, "memory allocated before the checkpoint shouldn't be reused");
+}
+
+#[test] fn scope() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 64]> = MaybeUninit::uninit();
+    let mut alloc = Bump::from_array(&mut buffer);
+
+    for _ in 0 .. 16 {
+        let sum = alloc.scope(|alloc| {
+            let a = ABox::try_new_in([1u32; 8], alloc).unwrap();
+            a.iter().sum::<u32>()
+        });
+        assert_eq!(8, sum);
+    }
+    assert_eq!(64, alloc.available());
+}
+
+#[cfg(debug_assertions)]
+#[test] #[should_panic = "allocations made since the checkpoint are still outstanding"] fn rewind_outstanding() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 64]> = MaybeUninit::uninit();
+    let mut alloc = core::mem::ManuallyDrop::new(Bump::from_array(&mut buffer)); // don't double panic in `Drop`
+    let checkpoint = alloc.checkpoint();
+    core::mem::forget(ABox::try_new_in(1u32, &*alloc).unwrap());
+    alloc.rewind(checkpoint);
+}
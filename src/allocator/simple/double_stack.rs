@@ -0,0 +1,437 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::fmt::{self, Debug, Formatter};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+
+
+
+/// Bump-allocate from both ends of a slice of memory.
+///
+/// [`split`](Self::split) into a [`DoubleStackBottom`] and a [`DoubleStackTop`] handle to allocate with.
+/// The bottom grows upwards from the start of the buffer, the top grows downwards from the end of the buffer.
+/// An allocation that would make the two ends meet fails, even if the other end could be rewound to make room.
+///
+/// As with [`Bump`](super::Bump), memory is only reclaimed in bulk, by [`rewind`](DoubleStackBottom::rewind)ing either end to a [`checkpoint`](DoubleStackBottom::checkpoint).
+pub struct DoubleStack<'a> {
+    base:       NonNull<MaybeUninit<u8>>,
+    capacity:   usize,
+    bottom:     Cell<usize>, // offset of the end of the bottom's allocations
+    top:        Cell<usize>, // offset of the start of the top's allocations
+    phantom:    PhantomData<&'a mut [MaybeUninit<u8>]>,
+}
+
+/// The upwards growing end of a [`DoubleStack`].
+pub struct DoubleStackBottom<'s> {
+    stack:      &'s DoubleStack<'s>,
+    #[cfg(debug_assertions)] outstanding_allocs: Cell<usize>,
+}
+
+/// The downwards growing end of a [`DoubleStack`].
+pub struct DoubleStackTop<'s> {
+    stack:      &'s DoubleStack<'s>,
+    #[cfg(debug_assertions)] outstanding_allocs: Cell<usize>,
+}
+
+/// A saved [`DoubleStackBottom`] or [`DoubleStackTop`] state, to later `rewind` that same end back to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DoubleStackCheckpoint {
+    offset: usize,
+    #[cfg(debug_assertions)] outstanding_allocs: usize,
+}
+
+// SAFETY: ✔️ `DoubleStack` is semantically a `Cell<&'a mut [MaybeUninit<u8>]>`, which is `Send`
+unsafe impl Send for DoubleStack<'_> {}
+
+impl<'a> Debug for DoubleStack<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "DoubleStack {{ bottom: [...{} bytes], free: [...{} bytes], top: [...{} bytes] }}", self.bottom.get(), self.available(), self.capacity - self.top.get())
+    }
+}
+
+impl Debug for DoubleStackBottom<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        #[cfg(    debug_assertions )] return write!(f, "DoubleStackBottom {{ buffer: [...{} bytes], outstanding_allocs: {} }}", self.stack.available(), self.outstanding_allocs.get());
+        #[cfg(not(debug_assertions))] return write!(f, "DoubleStackBottom {{ buffer: [...{} bytes], outstanding_allocs: ?? }}", self.stack.available());
+    }
+}
+
+impl Debug for DoubleStackTop<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        #[cfg(    debug_assertions )] return write!(f, "DoubleStackTop {{ buffer: [...{} bytes], outstanding_allocs: {} }}", self.stack.available(), self.outstanding_allocs.get());
+        #[cfg(not(debug_assertions))] return write!(f, "DoubleStackTop {{ buffer: [...{} bytes], outstanding_allocs: ?? }}", self.stack.available());
+    }
+}
+
+impl<'a> DoubleStack<'a> {
+    pub fn from_array<const N: usize>(array: &'a mut MaybeUninit<[MaybeUninit<u8>; N]>) -> Self {
+        // SAFETY: ✔️ supposedly safe per <https://doc.rust-lang.org/core/mem/union.MaybeUninit.html#initializing-an-array-element-by-element>
+        let array : &mut [MaybeUninit<u8>; N] = unsafe { array.assume_init_mut() };
+        Self::new(&mut array[..])
+    }
+
+    pub fn new(buffer: &'a mut [MaybeUninit<u8>]) -> Self {
+        let capacity = buffer.len();
+        Self {
+            capacity,
+            base:       NonNull::from(buffer).cast(),
+            bottom:     Cell::new(0),
+            top:        Cell::new(capacity),
+            phantom:    PhantomData,
+        }
+    }
+
+    /// Get handles to allocate from the bottom and top ends of the buffer.
+    ///
+    /// Memory allocated by previous handles remains allocated until [`reset`](Self::reset).
+    pub fn split(&mut self) -> (DoubleStackBottom<'_>, DoubleStackTop<'_>) {
+        (
+            DoubleStackBottom { stack: self, #[cfg(debug_assertions)] outstanding_allocs: Cell::new(0) },
+            DoubleStackTop    { stack: self, #[cfg(debug_assertions)] outstanding_allocs: Cell::new(0) },
+        )
+    }
+
+    /// Release all memory allocated from either end, making it available for reuse.
+    ///
+    /// This takes `&mut self`, so no handles from [`split`](Self::split) (and thus no allocations borrowing them) can still be alive.
+    pub fn reset(&mut self) {
+        self.bottom.set(0);
+        self.top.set(self.capacity);
+    }
+
+    fn available(&self) -> usize { self.top.get() - self.bottom.get() }
+}
+
+impl<'s> DoubleStackBottom<'s> {
+    /// Save the current state of the bottom end, to [`rewind`](Self::rewind) back to later.
+    pub fn checkpoint(&self) -> DoubleStackCheckpoint {
+        DoubleStackCheckpoint {
+            offset: self.stack.bottom.get(),
+            #[cfg(debug_assertions)] outstanding_allocs: self.outstanding_allocs.get(),
+        }
+    }
+
+    /// Release all memory allocated from the bottom end since `checkpoint` was taken, making it available for reuse by either end.
+    ///
+    /// This takes `&mut self`, so no borrowing allocations (e.g. <code>[ABox](crate::boxed::ABox)&lt;T, &amp;DoubleStackBottom&gt;</code>) can still be alive.
+    /// Allocations from the top end are unaffected.
+    ///
+    /// ### Panics
+    /// *   If `checkpoint` is newer than the bottom end's current state (e.g. it was already rewound past, or `checkpoint` came from another end.)
+    /// *   (debug builds only) If allocations made since `checkpoint` are still outstanding - e.g. they were [`forget`](core::mem::forget)ten.
+    pub fn rewind(&mut self, checkpoint: DoubleStackCheckpoint) {
+        assert!(checkpoint.offset <= self.stack.bottom.get(), "allocator::simple::DoubleStackBottom::rewind: checkpoint is newer than the allocator's current state");
+        #[cfg(debug_assertions)] assert!(self.outstanding_allocs.get() <= checkpoint.outstanding_allocs, "allocator::simple::DoubleStackBottom::rewind: allocations made since the checkpoint are still outstanding");
+        self.stack.bottom.set(checkpoint.offset);
+    }
+}
+
+impl<'s> DoubleStackTop<'s> {
+    /// Save the current state of the top end, to [`rewind`](Self::rewind) back to later.
+    pub fn checkpoint(&self) -> DoubleStackCheckpoint {
+        DoubleStackCheckpoint {
+            offset: self.stack.top.get(),
+            #[cfg(debug_assertions)] outstanding_allocs: self.outstanding_allocs.get(),
+        }
+    }
+
+    /// Release all memory allocated from the top end since `checkpoint` was taken, making it available for reuse by either end.
+    ///
+    /// This takes `&mut self`, so no borrowing allocations (e.g. <code>[ABox](crate::boxed::ABox)&lt;T, &amp;DoubleStackTop&gt;</code>) can still be alive.
+    /// Allocations from the bottom end are unaffected.
+    ///
+    /// ### Panics
+    /// *   If `checkpoint` is newer than the top end's current state (e.g. it was already rewound past, or `checkpoint` came from another end.)
+    /// *   (debug builds only) If allocations made since `checkpoint` are still outstanding - e.g. they were [`forget`](core::mem::forget)ten.
+    pub fn rewind(&mut self, checkpoint: DoubleStackCheckpoint) {
+        assert!(checkpoint.offset >= self.stack.top.get() && checkpoint.offset <= self.stack.capacity, "allocator::simple::DoubleStackTop::rewind: checkpoint is newer than the allocator's current state");
+        #[cfg(debug_assertions)] assert!(self.outstanding_allocs.get() <= checkpoint.outstanding_allocs, "allocator::simple::DoubleStackTop::rewind: allocations made since the checkpoint are still outstanding");
+        self.stack.top.set(checkpoint.offset);
+    }
+}
+
+impl Drop for DoubleStackBottom<'_> {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)] assert_eq!(0, self.outstanding_allocs.get(), "allocator::simple::DoubleStackBottom has outstanding allocations");
+    }
+}
+
+impl Drop for DoubleStackTop<'_> {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)] assert_eq!(0, self.outstanding_allocs.get(), "allocator::simple::DoubleStackTop has outstanding allocations");
+    }
+}
+
+
+
+// meta::*
+
+impl Meta for DoubleStackBottom<'_> {
+    type Error                  = ();
+    const MAX_ALIGN : Alignment = Alignment::MAX;
+    const MAX_SIZE  : usize     = usize::MAX;
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl Meta for DoubleStackTop<'_> {
+    type Error                  = ();
+    const MAX_ALIGN : Alignment = Alignment::MAX;
+    const MAX_SIZE  : usize     = usize::MAX;
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl ZstSupported for DoubleStackBottom<'_> {}
+impl ZstSupported for DoubleStackTop<'_> {}
+
+// SAFETY: ✔️ ZSTs are dangling pointers that never touch the buffer
+unsafe impl ZstInfalliable for DoubleStackBottom<'_> {}
+// SAFETY: ✔️ ZSTs are dangling pointers that never touch the buffer
+unsafe impl ZstInfalliable for DoubleStackTop<'_> {}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations are carved from between `bottom` and `top`, which no live allocation overlaps
+//
+unsafe impl fat::Alloc for DoubleStackBottom<'_> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<crate::AllocNN, Self::Error> {
+        let align = layout.align();
+        let size = layout.size();
+
+        let _ = Alignment::from(layout); // XXX: possibly hint to compiler that layout is nonzero
+        if size == 0 {
+            #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+            return Ok(crate::util::nn::dangling(layout));
+        }
+
+        let stack = self.stack;
+        let bottom = stack.bottom.get();
+        let align_mask = align.wrapping_sub(1);
+        let misalign = align_mask & (stack.base.as_ptr() as usize).wrapping_add(bottom);
+        let skip = align.wrapping_sub(misalign) & align_mask;
+        let start = bottom.saturating_add(skip);
+        let end = start.saturating_add(size);
+        if end > stack.top.get() { return Err(()) } // ≈ OOM
+
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+        stack.bottom.set(end);
+        // SAFETY: ✔️ `start < end <= top <= capacity`, so this stays within `buffer`
+        Ok(unsafe { NonNull::new_unchecked(stack.base.as_ptr().add(start)) })
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations are carved from between `bottom` and `top`, which no live allocation overlaps
+//
+unsafe impl fat::Alloc for DoubleStackTop<'_> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<crate::AllocNN, Self::Error> {
+        let align = layout.align();
+        let size = layout.size();
+
+        let _ = Alignment::from(layout); // XXX: possibly hint to compiler that layout is nonzero
+        if size == 0 {
+            #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+            return Ok(crate::util::nn::dangling(layout));
+        }
+
+        let stack = self.stack;
+        let top = stack.top.get();
+        let start = top.checked_sub(size).ok_or(())?; // ≈ OOM
+        let misalign = align.wrapping_sub(1) & (stack.base.as_ptr() as usize).wrapping_add(start);
+        let start = start.checked_sub(misalign).ok_or(())?; // ≈ OOM
+        if start < stack.bottom.get() { return Err(()) } // ≈ OOM
+
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+        stack.top.set(start);
+        // SAFETY: ✔️ `bottom <= start < top <= capacity`, so this stays within `buffer`
+        Ok(unsafe { NonNull::new_unchecked(stack.base.as_ptr().add(start)) })
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ freeing is a no-op
+//
+unsafe impl fat::Free for DoubleStackBottom<'_> {
+    unsafe fn free(&self, _ptr: crate::AllocNN, _layout: Layout) {
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() - 1);
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ freeing is a no-op
+//
+unsafe impl fat::Free for DoubleStackTop<'_> {
+    unsafe fn free(&self, _ptr: crate::AllocNN, _layout: Layout) {
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() - 1);
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ only the most recent allocation is resized in place, as nothing lies between it and `top`
+//
+unsafe impl fat::Realloc for DoubleStackBottom<'_> {
+    unsafe fn realloc_uninit(&self, ptr: crate::AllocNN, old_layout: Layout, new_layout: Layout) -> Result<crate::AllocNN, Self::Error> {
+        if old_layout == new_layout { return Ok(ptr) }
+
+        // In-place fast path:  if `ptr` was the most recent allocation, and is already sufficiently aligned, just move the end of it.
+        // ZSTs are dangling, and thus never the most recent allocation.
+        let stack = self.stack;
+        let start = (ptr.as_ptr() as usize).wrapping_sub(stack.base.as_ptr() as usize);
+        if old_layout.size() != 0 && start.wrapping_add(old_layout.size()) == stack.bottom.get() && (ptr.as_ptr() as usize) % new_layout.align() == 0 {
+            // Growing in place requires strictly less memory than allocating anew past the end of `ptr`, so there's no point falling back on failure.
+            let end = start.checked_add(new_layout.size()).ok_or(())?;
+            if end > stack.top.get() { return Err(()) } // ≈ OOM
+            stack.bottom.set(end);
+            return Ok(ptr);
+        }
+
+        // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        unsafe { fat::realloc_by_copy(self, self, ptr, old_layout, new_layout) }
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations grow downwards, so resizing always allocates anew (the default)
+//
+unsafe impl fat::Realloc for DoubleStackTop<'_> {}
+
+#[cfg(allocator_api = "1.50")] #[no_implicit_prelude] mod cleanroom {
+    use crate::impls;
+    use super::{DoubleStackBottom, DoubleStackTop};
+
+    impls! {
+        unsafe impl['s] core::alloc::Allocator(unstable 1.50) for DoubleStackBottom<'s> => ialloc::fat::Realloc;
+        unsafe impl['s] core::alloc::Allocator(unstable 1.50) for DoubleStackTop<'s>    => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[test] fn test_quick() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 4096]> = MaybeUninit::uninit();
+    let mut stack = DoubleStack::from_array(&mut buffer);
+    let (bottom, top) = stack.split();
+
+    let b = ABox::try_new_in(1u32, &bottom).unwrap();
+    let t = ABox::try_new_in(2u32, &top).unwrap();
+    assert!((&*b as *const u32) < (&*t as *const u32));
+    assert_eq!(1, *b);
+    assert_eq!(2, *t);
+}
+
+#[test] fn ends_meet() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 64]> = MaybeUninit::uninit();
+    let mut stack = DoubleStack::from_array(&mut buffer);
+    let (bottom, top) = stack.split();
+
+    let b = ABox::try_new_in([1u8; 32], &bottom).unwrap();
+    let t = ABox::try_new_in([2u8; 16], &top).unwrap();
+    assert!(ABox::try_new_in([3u8; 17], &bottom).is_err());
+    assert!(ABox::try_new_in([3u8; 17], &top).is_err());
+    let b2 = ABox::try_new_in([4u8; 16], &bottom).unwrap();
+    assert!(ABox::try_new_in(5u8, &top).is_err());
+    assert!(ABox::try_new_in(5u8, &bottom).is_err());
+    assert_eq!([1u8; 32], *b);
+    assert_eq!([2u8; 16], *t);
+    assert_eq!([4u8; 16], *b2);
+}
+
+#[test] fn checkpoint_rewind() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 64]> = MaybeUninit::uninit();
+    let mut stack = DoubleStack::from_array(&mut buffer);
+    let (mut bottom, mut top) = stack.split();
+
+    let level = ABox::try_new_in([1u8; 16], &bottom).unwrap();
+    let level_ptr = &*level as *const [u8; 16];
+    drop(level);
+    let bottom_checkpoint = bottom.checkpoint();
+    let top_checkpoint = top.checkpoint();
+
+    let temp = ABox::try_new_in([2u8; 32], &top).unwrap();
+    let temp_ptr = &*temp as *const [u8; 32];
+    assert!(ABox::try_new_in([3u8; 32], &bottom).is_err());
+    drop(temp);
+
+    top.rewind(top_checkpoint);
+    let temp = ABox::try_new_in([4u8; 32], &top).unwrap();
+    assert_eq!(temp_ptr, &*temp as *const [u8; 32], "rewound memory should be reused");
+    drop(temp);
+    top.rewind(top_checkpoint);
+
+    let level = ABox::try_new_in([5u8; 48], &bottom).unwrap();
+    assert_ne!(level_ptr as *const u8, &*level as *const [u8; 48] as *const u8, "memory allocated before the checkpoint shouldn't be reused");
+    drop(level);
+    bottom.rewind(bottom_checkpoint);
+
+    drop((bottom, top));
+    stack.reset();
+    let (bottom, _top) = stack.split();
+    let level = ABox::try_new_in([6u8; 64], &bottom).unwrap();
+    assert_eq!(level_ptr as *const u8, &*level as *const [u8; 64] as *const u8, "reset memory should be reused");
+}
+
+#[test] fn realloc_in_place() {
+    use crate::fat::*;
+
+    let mut buffer : MaybeUninit<[_; 4096]> = MaybeUninit::uninit();
+    let mut stack = DoubleStack::from_array(&mut buffer);
+    let (bottom, _top) = stack.split();
+    let l16  = Layout::from_size_align(16, 4).unwrap();
+    let l64  = Layout::from_size_align(64, 4).unwrap();
+
+    let a = bottom.alloc_uninit(l16).unwrap();
+    // SAFETY: ✔️ `a` belongs to `bottom` and was allocated with `l16`
+    let a2 = unsafe { bottom.realloc_uninit(a, l16, l64) }.unwrap();
+    assert_eq!(a, a2, "most recent allocation should grow in place");
+    // SAFETY: ✔️ `a2` belongs to `bottom` and was reallocated with `l64`
+    unsafe { bottom.free(a2, l64) };
+}
+
+#[cfg(debug_assertions)]
+#[test] #[should_panic = "allocations made since the checkpoint are still outstanding"] fn rewind_outstanding() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 64]> = MaybeUninit::uninit();
+    let mut stack = DoubleStack::from_array(&mut buffer);
+    let (_bottom, top) = stack.split();
+    let mut top = core::mem::ManuallyDrop::new(top); // don't double panic in `Drop`
+    let checkpoint = top.checkpoint();
+    core::mem::forget(ABox::try_new_in(1u32, &*top).unwrap());
+    top.rewind(checkpoint);
+}
+
+
+
+#[cfg(test)] mod fat_bottom {
+    use super::*;
+    #[test] fn fat_alignment()          { fat::test::alignment(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().0) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().0) }
+    #[test] fn fat_uninit()             { unsafe { fat::test::uninit_alloc_unsound(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().0) } }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().0) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().0) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().0) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().0) }
+}
+
+#[cfg(test)] mod fat_top {
+    use super::*;
+    #[test] fn fat_alignment()          { fat::test::alignment(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().1) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().1) }
+    #[test] fn fat_uninit()             { unsafe { fat::test::uninit_alloc_unsound(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().1) } }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().1) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().1) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().1) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(DoubleStack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF)))).split().1) }
+}
@@ -31,9 +31,9 @@ use core::ptr::NonNull;
 /// Freeing and reallocating memory is constant time.
 ///
 /// ## Alternatives
-/// *   I should write a `FixedPoolFreeList` allocator that (ab)uses unallocated slots as a linked list of free entries.<br>
-///     This will be `O(1)` at the expense of additional complexity and minimum element size.
-/// *   I should write non-`Fixed*` variants which heap allocate additional pools as necessary.
+/// *   [`FixedPoolFreeList`](super::FixedPoolFreeList) (ab)uses unallocated slots as a linked list of free entries.<br>
+///     This is `O(1)` at the expense of additional complexity and minimum element size.
+/// *   [`PoolFreeList`](super::PoolFreeList) allocates additional chunks from another allocator as necessary.
 pub struct FixedPoolLinearProbe<const A: usize, const B: usize, const N: usize> where [(); A] : ValidAlignLessThan1GiB {
     buffer: [Element<A, B>; N],
     state:  [Cell<State>; N], // Lame: wastes 7 bits per element at the moment.  Would be nice to instead use e.g. `[u32; N/32]` but const generics aren't that awesome yet.
@@ -99,6 +99,11 @@ impl<const A: usize, const B: usize, const N: usize> Meta for &'_ FixedPoolLinea
 
 impl<const A: usize, const B: usize, const N: usize> ZstSupported for &'_ FixedPoolLinearProbe<A, B, N> where [(); A] : ValidAlignLessThan1GiB {}
 
+// SAFETY: ✔️ every allocation is an element of `buffer`
+unsafe impl<const A: usize, const B: usize, const N: usize> Owns for FixedPoolLinearProbe<A, B, N> where [(); A] : ValidAlignLessThan1GiB {
+    fn owns(&self, ptr: AllocNN) -> bool { self.buffer.as_ptr_range().contains(&ptr.as_ptr().cast_const().cast()) }
+}
+
 
 
 // thin::*
@@ -0,0 +1,238 @@
+use crate::*;
+use crate::align::alignn::_align_impl::ValidAlignLessThan1GiB;
+use crate::meta::*;
+
+use core::cell::*;
+use core::fmt::{self, Debug, Formatter};
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+
+
+
+/// Pool-allocate fixed size elements from a fixed region of memory, tracking free elements with an intrusive linked list
+///
+/// ## Generic Parameters
+/// *   `A` - alignment of pool elements in bytes
+/// *   `B` - size of pool elements in bytes
+/// *   `N` - number of elements in pool (must be less than [`u32::MAX`])
+///
+/// ## Allocation Scaling Behavior
+///
+/// Allocating, freeing, and reallocating memory is constant time.
+///
+/// Unallocated slots are (ab)used to store the index of the next free slot.
+/// As such, elements are always at least [`u32`]-sized and [`u32`]-aligned, even if `A` and `B` are smaller.
+/// Slots that have never been allocated aren't linked at all - they're handed out in order once the free list runs dry -
+/// which keeps construction `O(1)` and `const`.
+///
+/// ## Alternatives
+/// *   [`FixedPoolLinearProbe`](super::FixedPoolLinearProbe) has no minimum element size and detects double frees in debug builds, at the cost of `O(N)` worst case allocation.
+/// *   [`PoolFreeList`](super::PoolFreeList) allocates additional chunks from another allocator as necessary.
+pub struct FixedPoolFreeList<const A: usize, const B: usize, const N: usize> where [(); A] : ValidAlignLessThan1GiB {
+    buffer: [Element<A, B>; N],
+    free:   Cell<u32>,      // head of the intrusive free list, or `NONE`
+    unused: Cell<usize>,    // `buffer[unused..]` has never been allocated (and isn't part of the `free` list)
+}
+
+impl<const A: usize, const B: usize, const N: usize> FixedPoolFreeList<A, B, N> where [(); A] : ValidAlignLessThan1GiB {
+    const ASSERT_N_FITS_U32 : () = assert!(N < NONE as usize, "FixedPoolFreeList<A, B, N> requires N < u32::MAX");
+
+    pub const fn new() -> Self {
+        let _ = Self::ASSERT_N_FITS_U32;
+        Self {
+            #[allow(clippy::uninit_assumed_init)] // the generics confuse clippy, but [UnsafeCell<MaybeUninit<T>>; N] should be safe... probably!
+            // SAFETY: ⚠️ UnsafeCell<MaybeUninit<T>> is always "init" even if T isn't, ergo this should be safe?
+            buffer: unsafe { MaybeUninit::uninit().assume_init() },
+            free:   Cell::new(NONE),
+            unused: Cell::new(0),
+        }
+    }
+
+    /// Get a pointer to the start of the slot at `index`.
+    fn slot(&self, index: usize) -> *mut Slot<B> { self.buffer[index].get().cast() }
+
+    /// Get the index into `buffer` of `ptr`, a previously allocated element of `buffer`.
+    ///
+    /// ### Safety
+    /// *   `ptr` must belong to `self` or this is straight up undefined behavior.
+    unsafe fn index_of(&self, ptr: AllocNN) -> usize {
+        // SAFETY: ✔️ `ptr` should belong to `self.buffer` per documented safety precondition, ergo `offset_from` should be safe.
+        let index = unsafe { ptr.as_ptr().cast::<Element<A, B>>().offset_from(self.buffer.as_ptr()) } as usize;
+        if cfg!(debug_assertions) && index >= self.unused.get() { bug::ub::invalid_ptr_for_allocator(ptr) }
+        index
+    }
+}
+
+impl<const A: usize, const B: usize, const N: usize> Default for FixedPoolFreeList<A, B, N> where [(); A] : ValidAlignLessThan1GiB {
+    fn default() -> Self { Self::new() }
+}
+
+impl<const A: usize, const B: usize, const N: usize> Debug for FixedPoolFreeList<A, B, N> where [(); A] : ValidAlignLessThan1GiB {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { write!(f, "FixedPoolFreeList<{A}, {B}, {N}> {{ ... }}") }
+}
+
+
+
+// meta::*
+
+impl<const A: usize, const B: usize, const N: usize> Meta for &'_ FixedPoolFreeList<A, B, N> where [(); A] : ValidAlignLessThan1GiB {
+    type Error                  = ();
+    const MAX_ALIGN : Alignment = Alignment::constant(A);
+    const MAX_SIZE  : usize     = B;
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl<const A: usize, const B: usize, const N: usize> ZstSupported for &'_ FixedPoolFreeList<A, B, N> where [(); A] : ValidAlignLessThan1GiB {}
+
+// SAFETY: ✔️ every allocation is an element of `buffer`
+unsafe impl<const A: usize, const B: usize, const N: usize> Owns for FixedPoolFreeList<A, B, N> where [(); A] : ValidAlignLessThan1GiB {
+    fn owns(&self, ptr: AllocNN) -> bool { self.buffer.as_ptr_range().contains(&ptr.as_ptr().cast_const().cast()) }
+}
+
+
+
+// thin::*
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ if `alloc_uninit` succeeds:
+//  • We must return a pointer to at least `size` bytes.  As all elements of `self.buffer` are only `B` bytes long (or larger, never smaller, thanks to `Slot`), we reject `size` above that [1].
+//  • Allocations should have at least alignment `min(MAX_ALIGN, ...)`.  All elements of `self.buffer` have alignment `A` = `MAX_ALIGN` via `Element`'s definition including `AlignN` [2].
+//  • The bucket should not have already been allocated - it's either popped from the free list, or has never been allocated [3].
+//  • The allocations should remain valid for the lifetime of `Self`.  Ergo, we must implement this on a *reference* to FixedPoolFreeList, not a value of it.
+//
+unsafe impl<const A: usize, const B: usize, const N: usize> thin::Alloc for &'_ FixedPoolFreeList<A, B, N> where [(); A] : ValidAlignLessThan1GiB {
+    fn alloc_uninit(&self, size: usize) -> Result<AllocNN, Self::Error> {
+        if size > B { return Err(()) } // [1]
+
+        let index = match self.free.get() {
+            NONE => { // [3]
+                let index = self.unused.get();
+                if index >= N { return Err(()) }
+                self.unused.set(index + 1);
+                index
+            },
+            free => { // [3]
+                let index = free as usize;
+                // SAFETY: ✔️ `index` is on the free list, ergo the slot is unallocated and its first bytes store the next free index
+                let next = unsafe { self.slot(index).cast::<u32>().read() };
+                self.free.set(next);
+                index
+            },
+        };
+
+        NonNull::new(self.slot(index).cast()).ok_or(())
+    }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ `free` should be safe to call on any allocated `ptr` belonging to `self`
+//
+unsafe impl<const A: usize, const B: usize, const N: usize> thin::Free for &'_ FixedPoolFreeList<A, B, N> where [(); A] : ValidAlignLessThan1GiB {
+    unsafe fn free(&self, ptr: AllocNN) {
+        // SAFETY: ✔️ `ptr` should belong to `self` per `thin::Free::free`'s documented safety precondition
+        let index = unsafe { self.index_of(ptr) };
+        // SAFETY: ✔️ the slot is no longer allocated, and `Slot` guarantees room for a properly aligned `u32`
+        unsafe { self.slot(index).cast::<u32>().write(self.free.get()) };
+        self.free.set(index as u32);
+    }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ if `realloc_uninit` succeeds with defined behavior:
+//  • We must return a pointer to at least `size` bytes.  As all elements of `self.buffer` are at least `B` bytes long, we reject `size` above that [4].
+//  • Allocations should have at least alignment `min(MAX_ALIGN, ...)`.  All elements of `self.buffer` have the same alignment, so returning `ptr` again provides the same guarantee [5].
+//  • The allocations should remain valid for the lifetime of `Self`.  Ergo, we must implement this on a *reference* to FixedPoolFreeList, not a value of it.
+//
+unsafe impl<const A: usize, const B: usize, const N: usize> thin::Realloc for &'_ FixedPoolFreeList<A, B, N> where [(); A] : ValidAlignLessThan1GiB {
+    const CAN_REALLOC_ZEROED : bool = false;
+
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        // SAFETY: ✔️ `ptr` should belong to `self` per `thin::Realloc::realloc_uninit`'s documented safety precondition
+        let _ = unsafe { self.index_of(ptr) };
+        if new_size > B { return Err(()) } // [4]
+        Ok(ptr) // [5]
+    }
+
+    unsafe fn realloc_zeroed(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        // SAFETY: ✔️ trivially safe - always failing is allowed
+        let _ = (ptr, new_size);
+        Err(())
+    }
+}
+
+#[no_implicit_prelude] mod cleanroom {
+    use crate::impls;
+    use super::{FixedPoolFreeList, ValidAlignLessThan1GiB};
+
+    impls! {
+        unsafe impl['a, const A: usize, const B: usize, const N: usize] ialloc::fat::Alloc      for &'a FixedPoolFreeList<A, B, N> where [[(); A] : ValidAlignLessThan1GiB] => ialloc::thin::Alloc;
+        unsafe impl['a, const A: usize, const B: usize, const N: usize] ialloc::fat::Realloc    for &'a FixedPoolFreeList<A, B, N> where [[(); A] : ValidAlignLessThan1GiB] => ialloc::thin::Realloc;
+        unsafe impl['a, const A: usize, const B: usize, const N: usize] ialloc::fat::Free       for &'a FixedPoolFreeList<A, B, N> where [[(); A] : ValidAlignLessThan1GiB] => ialloc::thin::Free;
+    }
+}
+
+
+
+const NONE : u32 = u32::MAX;
+
+type Element<const A : usize, const B : usize> = UnsafeCell<MaybeUninit<AlignN<A, Slot<B>>>>; // [2]
+
+/// Either `B` bytes of allocated data, or the index of the next free slot.
+#[allow(dead_code)] // never read through the fields - only used for size and alignment
+#[repr(C)] union Slot<const B: usize> { data: [u8; B], next: u32 }
+
+
+
+#[test] fn test_quick() {
+    use crate::boxed::ABox;
+
+    let pool = FixedPoolFreeList::<4, 4, 1024>::new();
+    let mut next = 0_u32;
+    for _ in 0 .. 10 {
+        assert!(ABox::try_new_in([0u8; 8], &pool).is_err(), "element too big to fit in pool");
+        let _integers = [(); 1024].map(|_| {
+            next += 1;
+            ABox::try_new_in(next, &pool).unwrap()
+        });
+        assert!(ABox::try_new_in(0u32, &pool).is_err(), "pool out of elements");
+        let _ = &_integers[0];
+        #[cfg(feature = "std")] std::dbg!(&_integers[0]);
+    }
+}
+
+#[test] fn test_reuse_lifo() {
+    use thin::{Alloc, Free};
+
+    let pool = FixedPoolFreeList::<1, 1, 4>::new();
+    let pool = &pool;
+    let a = pool.alloc_uninit(1).unwrap();
+    let b = pool.alloc_uninit(1).unwrap();
+    let c = pool.alloc_uninit(1).unwrap();
+    // SAFETY: ✔️ `a` belongs to `pool` and is freed once
+    unsafe { pool.free(a) };
+    // SAFETY: ✔️ `c` belongs to `pool` and is freed once
+    unsafe { pool.free(c) };
+    assert_eq!(c, pool.alloc_uninit(1).unwrap());
+    assert_eq!(a, pool.alloc_uninit(1).unwrap());
+    let d = pool.alloc_uninit(1).unwrap();
+    assert!(pool.alloc_uninit(1).is_err(), "pool out of elements");
+    // SAFETY: ✔️ all allocations belong to `pool` and are freed once
+    for p in [a, b, c, d] { unsafe { pool.free(p) } }
+}
+
+#[test] fn thin_alignment()         { thin::test::alignment(&FixedPoolFreeList::<4, 4, 1024>::new()) }
+#[test] fn thin_edge_case_sizes()   { thin::test::edge_case_sizes(&FixedPoolFreeList::<4, 4, 1024>::new()) }
+#[test] fn thin_nullable()          { thin::test::nullable(&FixedPoolFreeList::<4, 4, 1024>::new()) }
+#[test] fn thin_uninit()            { unsafe { thin::test::uninit_alloc_unsound(&FixedPoolFreeList::<4, 4, 128>::new()) } }
+#[test] fn thin_uninit_realloc()    { thin::test::uninit_realloc(&FixedPoolFreeList::<4, 4, 128>::new()) }
+#[test] fn thin_zeroed()            { thin::test::zeroed_alloc(&FixedPoolFreeList::<4, 4, 128>::new()) }
+#[test] fn thin_zeroed_realloc()    { thin::test::zeroed_realloc(&FixedPoolFreeList::<4, 4, 128>::new()) }
+#[test] fn thin_zst_support()       { thin::test::zst_supported_accurate(&FixedPoolFreeList::<4, 4, 1024>::new()) }
+
+#[test] fn fat_alignment()          { fat::test::alignment(&FixedPoolFreeList::<4, 4, 1024>::new()) }
+#[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(&FixedPoolFreeList::<4, 4, 1024>::new()) }
+#[test] fn fat_uninit()             { unsafe { fat::test::uninit_alloc_unsound(&FixedPoolFreeList::<4, 4, 128>::new()) } }
+#[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(&FixedPoolFreeList::<4, 4, 128>::new()) }
+#[test] fn fat_zeroed()             { fat::test::zeroed_alloc(&FixedPoolFreeList::<4, 4, 128>::new()) }
+#[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(&FixedPoolFreeList::<4, 4, 128>::new()) }
+#[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(&FixedPoolFreeList::<4, 4, 1024>::new()) }
@@ -0,0 +1,270 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::fmt::{self, Debug, Formatter};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+
+
+
+/// Bump-allocate temporaries that live for `N` frames, from a slice of memory split into `N` equally sized frames.
+///
+/// Allocations are made from the current frame.
+/// [`next_frame`](Self::next_frame) recycles the oldest frame, making it the current frame.
+///
+/// ### Panics
+/// [`next_frame`](Self::next_frame) panics if any allocation from the recycled frame is still live.
+/// Unlike most of this crate's bug checks, this check isn't limited to debug builds:  `next_frame` only takes `&self`, so skipping it would be unsound.
+pub struct FrameRing<'a, const N: usize> {
+    base:       NonNull<MaybeUninit<u8>>,
+    frame_size: usize,
+    current:    Cell<usize>,
+    used:       Cell<usize>, // bytes used of the current frame
+    live:       [Cell<usize>; N], // live allocations per frame
+    phantom:    PhantomData<&'a mut [MaybeUninit<u8>]>,
+}
+
+// SAFETY: ✔️ `FrameRing` is semantically a `Cell<&'a mut [MaybeUninit<u8>]>` plus some bookkeeping, which is `Send`
+unsafe impl<const N: usize> Send for FrameRing<'_, N> {}
+
+impl<'a, const N: usize> Debug for FrameRing<'a, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "FrameRing {{ frame: {} of {N}, buffer: [...{} bytes], live_allocs: {:?} }}", self.current.get(), self.available(), self.live.each_ref().map(Cell::get))
+    }
+}
+
+impl<'a, const N: usize> FrameRing<'a, N> {
+    pub fn from_array<const B: usize>(array: &'a mut MaybeUninit<[MaybeUninit<u8>; B]>) -> Self {
+        // SAFETY: ✔️ supposedly safe per <https://doc.rust-lang.org/core/mem/union.MaybeUninit.html#initializing-an-array-element-by-element>
+        let array : &mut [MaybeUninit<u8>; B] = unsafe { array.assume_init_mut() };
+        Self::new(&mut array[..])
+    }
+
+    /// Split `buffer` into `N` equally sized frames.  Any remainder is unused.
+    ///
+    /// ### Panics
+    /// *   If `N == 0`
+    pub fn new(buffer: &'a mut [MaybeUninit<u8>]) -> Self {
+        assert!(N > 0, "allocator::simple::FrameRing::new: `N` must be nonzero");
+        Self {
+            frame_size: buffer.len() / N,
+            base:       NonNull::from(buffer).cast(),
+            current:    Cell::new(0),
+            used:       Cell::new(0),
+            live:       [(); N].map(|_| Cell::new(0)),
+            phantom:    PhantomData,
+        }
+    }
+
+    /// Recycle the oldest frame, releasing all of its memory for reuse as the new current frame.
+    ///
+    /// ### Panics
+    /// *   If any allocation from the recycled frame is still live.
+    #[track_caller] pub fn next_frame(&self) {
+        let next = (self.current.get() + 1) % N;
+        let live = self.live[next].get();
+        if live != 0 { bug::recycled_frame_still_allocated(next, live) }
+        self.current.set(next);
+        self.used.set(0);
+    }
+
+    fn available(&self) -> usize { self.frame_size - self.used.get() }
+
+    fn frame_start(&self) -> usize { self.current.get() * self.frame_size }
+
+    /// Get the index of the frame `ptr` was allocated from.
+    fn frame_of(&self, ptr: AllocNN) -> usize {
+        let offset = (ptr.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize);
+        let frame = offset / self.frame_size;
+        if cfg!(debug_assertions) && (frame >= N || self.live[frame].get() == 0) { bug::ub::invalid_ptr_for_allocator(ptr) }
+        frame
+    }
+}
+
+impl<'a, const N: usize> Drop for FrameRing<'a, N> {
+    fn drop(&mut self) {
+        // As `'a` is likely nonstatic, this has a pretty serious chance of being a soundness bug - e.g. perhaps `ABox::into_inner` outlived `FrameRing`.
+        #[cfg(debug_assertions)] assert!(self.live.iter().all(|live| live.get() == 0), "allocator::simple::FrameRing has outstanding allocations");
+    }
+}
+
+
+
+// meta::*
+
+impl<'a, const N: usize> Meta for FrameRing<'a, N> {
+    type Error                  = ();
+    const MAX_ALIGN : Alignment = Alignment::MAX;
+    const MAX_SIZE  : usize     = usize::MAX;
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl<const N: usize> ZstSupported for FrameRing<'_, N> {}
+
+// SAFETY: ✔️ ZSTs are dangling pointers that never touch any frame
+unsafe impl<const N: usize> ZstInfalliable for FrameRing<'_, N> {}
+
+// SAFETY: ✔️ every non-zero-sized allocation lies within the frames
+unsafe impl<const N: usize> Owns for FrameRing<'_, N> {
+    fn owns(&self, ptr: AllocNN) -> bool { (ptr.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize) < self.frame_size * N }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations are carved from the unused tail of the current frame, and frames are only recycled without live allocations
+//
+unsafe impl<'a, const N: usize> fat::Alloc for FrameRing<'a, N> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<crate::AllocNN, Self::Error> {
+        let align = layout.align();
+        let size = layout.size();
+
+        let _ = Alignment::from(layout); // XXX: possibly hint to compiler that layout is nonzero
+        if size == 0 { return Ok(crate::util::nn::dangling(layout)) } // ZSTs belong to no frame
+
+        let frame_start = self.frame_start();
+        let used = self.used.get();
+        let align_mask = align.wrapping_sub(1);
+        let misalign = align_mask & (self.base.as_ptr() as usize).wrapping_add(frame_start).wrapping_add(used);
+        let skip = align.wrapping_sub(misalign) & align_mask;
+        let start = used.saturating_add(skip);
+        let end = start.saturating_add(size);
+        if end > self.frame_size { return Err(()) } // ≈ OOM
+
+        let live = &self.live[self.current.get()];
+        live.set(live.get() + 1);
+        self.used.set(end);
+        // SAFETY: ✔️ `start < end <= frame_size`, so this stays within the current frame of `buffer`
+        Ok(unsafe { NonNull::new_unchecked(self.base.as_ptr().add(frame_start + start)) })
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ freeing only updates bookkeeping - memory is reclaimed by `next_frame`
+//
+unsafe impl<'a, const N: usize> fat::Free for FrameRing<'a, N> {
+    unsafe fn free(&self, ptr: crate::AllocNN, layout: Layout) {
+        if layout.size() == 0 { return }
+        let live = &self.live[self.frame_of(ptr)];
+        live.set(live.get() - 1);
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ only the most recent allocation of the current frame is resized in place, as nothing lies after it
+//
+unsafe impl<'a, const N: usize> fat::Realloc for FrameRing<'a, N> {
+    unsafe fn realloc_uninit(&self, ptr: crate::AllocNN, old_layout: Layout, new_layout: Layout) -> Result<crate::AllocNN, Self::Error> {
+        if old_layout == new_layout { return Ok(ptr) }
+
+        // In-place fast path:  if `ptr` was the most recent allocation of the current frame, and is already sufficiently aligned, just move the end of it.
+        // ZSTs are dangling, and thus never the most recent allocation.
+        // Allocations from older frames wrap to a `start` past the end of the current frame, even if they happen to end where the current frame's `used` does.
+        let start = (ptr.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize).wrapping_sub(self.frame_start());
+        if old_layout.size() != 0 && new_layout.size() != 0 && start < self.frame_size && start + old_layout.size() == self.used.get() && (ptr.as_ptr() as usize) % new_layout.align() == 0 {
+            // Growing in place requires strictly less memory than allocating anew past the end of `ptr`, so there's no point falling back on failure.
+            let end = start.checked_add(new_layout.size()).ok_or(())?;
+            if end > self.frame_size { return Err(()) } // ≈ OOM
+            self.used.set(end);
+            return Ok(ptr);
+        }
+
+        // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        unsafe { fat::realloc_by_copy(self, self, ptr, old_layout, new_layout) }
+    }
+}
+
+#[no_implicit_prelude] mod cleanroom {
+    use crate::impls;
+    use super::FrameRing;
+
+    impls! {
+        unsafe impl[const N: usize]     core::alloc::GlobalAlloc for     FrameRing<'static, N> => ialloc::fat::Realloc;
+        unsafe impl['o, const N: usize] core::alloc::GlobalAlloc for &'o FrameRing<'static, N> => core::ops::Deref;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl['a, const N: usize] core::alloc::Allocator(unstable 1.50) for FrameRing<'a, N> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[test] fn test_quick() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 4096]> = MaybeUninit::uninit();
+    let alloc = FrameRing::<2>::from_array(&mut buffer);
+
+    let mut previous = None;
+    for frame in 0 .. 100u32 {
+        let current = ABox::try_new_in(frame, &alloc).unwrap();
+        while ABox::try_new_in([0u8; 64], &alloc).is_ok() {}
+        if let Some(previous) = previous.replace(current) { assert_eq!(frame - 1, *previous) }
+        alloc.next_frame(); // recycles the frame of the box `previous` just replaced
+    }
+}
+
+#[test] fn frames_are_recycled() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 3 * 64]> = MaybeUninit::uninit();
+    let alloc = FrameRing::<3>::from_array(&mut buffer);
+
+    let a = ABox::try_new_in([1u8; 64], &alloc).unwrap();
+    let a_ptr = &*a as *const [u8; 64];
+    assert!(ABox::try_new_in(2u8, &alloc).is_err(), "frame should be full");
+    drop(a);
+    alloc.next_frame();
+    let b = ABox::try_new_in([3u8; 64], &alloc).unwrap();
+    alloc.next_frame();
+    let c = ABox::try_new_in([4u8; 64], &alloc).unwrap();
+    alloc.next_frame();
+    let d = ABox::try_new_in([5u8; 64], &alloc).unwrap();
+    assert_eq!(a_ptr, &*d as *const [u8; 64], "oldest frame should be reused");
+    assert_eq!([3u8; 64], *b);
+    assert_eq!([4u8; 64], *c);
+}
+
+#[test] fn realloc_previous_frame() {
+    use crate::fat::*;
+
+    let mut buffer : MaybeUninit<[_; 2 * 64]> = MaybeUninit::uninit();
+    let alloc = FrameRing::<2>::from_array(&mut buffer);
+    let full = Layout::new::<[u8; 64]>();
+    let half = Layout::new::<[u8; 32]>();
+
+    let a = alloc.alloc_uninit(full).unwrap(); // ends exactly at the end of frame 0
+    alloc.next_frame();
+    // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `full`
+    let b = unsafe { alloc.realloc_uninit(a, full, half) }.expect("`a` isn't the most recent allocation of frame 1, so this should allocate anew");
+    assert_ne!(a, b);
+    // SAFETY: ✔️ `b` belongs to `alloc` and was reallocated with `half`
+    unsafe { alloc.free(b, half) };
+}
+
+#[test] #[should_panic = "tried to recycle frame 0, but 1 allocations from it are still live"] fn recycle_live() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 256]> = MaybeUninit::uninit();
+    let alloc = core::mem::ManuallyDrop::new(FrameRing::<2>::from_array(&mut buffer)); // don't double panic in `Drop`
+    let a = ABox::try_new_in(1u32, &*alloc).unwrap();
+    alloc.next_frame();
+    alloc.next_frame();
+    drop(a);
+}
+
+
+
+#[test] fn fat_alignment()          { fat::test::alignment(FrameRing::<2>::from_array(&mut MaybeUninit::new([(); 262144].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(FrameRing::<2>::from_array(&mut MaybeUninit::new([(); 262144].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_uninit()             { unsafe { fat::test::uninit_alloc_unsound(FrameRing::<2>::from_array(&mut MaybeUninit::new([(); 262144].map(|_| MaybeUninit::<u8>::new(0xFF))))) } }
+#[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(FrameRing::<2>::from_array(&mut MaybeUninit::new([(); 262144].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zeroed()             { fat::test::zeroed_alloc(FrameRing::<2>::from_array(&mut MaybeUninit::new([(); 262144].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(FrameRing::<2>::from_array(&mut MaybeUninit::new([(); 262144].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(FrameRing::<2>::from_array(&mut MaybeUninit::new([(); 262144].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
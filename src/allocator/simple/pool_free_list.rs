@@ -0,0 +1,366 @@
+use crate::*;
+use crate::align::alignn::_align_impl::ValidAlignLessThan1GiB;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::cell::*;
+use core::fmt::{self, Debug, Formatter};
+use core::mem::MaybeUninit;
+use core::ptr::{NonNull, addr_of_mut};
+
+
+
+/// Pool-allocate fixed size elements from chunks of memory allocated on demand from a `Backing` allocator
+///
+/// ## Generic Parameters
+/// *   `A` - alignment of pool elements in bytes
+/// *   `B` - size of pool elements in bytes
+/// *   `N` - number of elements per chunk
+/// *   `Backing` - the [`fat::Alloc`] + [`fat::Free`] allocator chunks are allocated from (e.g. [`alloc::Global`](crate::allocator::alloc::Global) or [`c::Malloc`](crate::allocator::c::Malloc))
+///
+/// ## Allocation Scaling Behavior
+///
+/// Allocating, freeing, and reallocating memory is constant time, except when a new chunk must be allocated,
+/// which is `O(N)` (every slot of the new chunk is linked into the free list.)
+///
+/// Like [`FixedPoolFreeList`](super::FixedPoolFreeList), unallocated slots are (ab)used as an intrusive linked list of free slots.
+/// As such, elements are always at least pointer-sized and pointer-aligned, even if `A` and `B` are smaller.
+///
+/// Chunks are only returned to `Backing` when the pool is dropped, or when explicitly requested via [`release_empty_chunks`](Self::release_empty_chunks).
+pub struct PoolFreeList<const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free> where [(); A] : ValidAlignLessThan1GiB {
+    backing:    Backing,
+    chunks:     Cell<Option<NonNull<Chunk<A, B, N>>>>,
+    free:       Cell<Option<NonNull<Slot<B>>>>,
+    #[cfg(debug_assertions)] outstanding_allocs: Cell<usize>,
+}
+
+impl<const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free> PoolFreeList<A, B, N, Backing> where [(); A] : ValidAlignLessThan1GiB {
+    const CHUNK_LAYOUT : Layout = Layout::new::<Chunk<A, B, N>>();
+
+    /// Create an empty pool, allocating chunks from `Backing::default()` as necessary.
+    pub fn new() -> Self where Backing : Default { Self::new_in(Backing::default()) }
+
+    /// Create an empty pool, allocating chunks from `backing` as necessary.
+    pub fn new_in(backing: Backing) -> Self {
+        Self {
+            backing,
+            chunks: Cell::new(None),
+            free:   Cell::new(None),
+            #[cfg(debug_assertions)] outstanding_allocs: Cell::new(0),
+        }
+    }
+
+    /// Number of chunks currently allocated from `Backing`.
+    pub fn chunks(&self) -> usize {
+        let mut n = 0;
+        let mut chunk = self.chunks.get();
+        while let Some(c) = chunk {
+            n += 1;
+            // SAFETY: ✔️ all chunks on `self.chunks` are live with initialized headers
+            chunk = unsafe { (*c.as_ptr()).next };
+        }
+        n
+    }
+
+    /// Return every chunk without any live allocations to `Backing`.
+    ///
+    /// This scales by <code>(free slots) × (chunks)</code> and is intended to be called occasionally (e.g. between levels or after a burst of allocations), not per-allocation.
+    ///
+    /// Returns the number of chunks released.
+    pub fn release_empty_chunks(&self) -> usize {
+        // Count free slots per chunk
+        let mut chunk = self.chunks.get();
+        while let Some(c) = chunk {
+            // SAFETY: ✔️ all chunks on `self.chunks` are live with initialized headers
+            unsafe { (*c.as_ptr()).free = 0 };
+            // SAFETY: ✔️ all chunks on `self.chunks` are live with initialized headers
+            chunk = unsafe { (*c.as_ptr()).next };
+        }
+        let mut slot = self.free.get();
+        while let Some(s) = slot {
+            let c = self.chunk_of(s).unwrap_or_else(|| bug::ub::invalid_ptr_for_allocator(s.cast::<MaybeUninit<u8>>()));
+            // SAFETY: ✔️ `c` is a live chunk with an initialized header
+            unsafe { (*c.as_ptr()).free += 1 };
+            // SAFETY: ✔️ `s` is on the free list, ergo it's unallocated and stores the next free slot
+            slot = unsafe { (*s.as_ptr()).next };
+        }
+
+        // Unlink slots belonging to empty chunks from the free list
+        let mut prev : Option<NonNull<Slot<B>>> = None;
+        let mut slot = self.free.get();
+        while let Some(s) = slot {
+            // SAFETY: ✔️ `s` is on the free list, ergo it's unallocated and stores the next free slot
+            let next = unsafe { (*s.as_ptr()).next };
+            let c = self.chunk_of(s).unwrap_or_else(|| bug::ub::invalid_ptr_for_allocator(s.cast::<MaybeUninit<u8>>()));
+            // SAFETY: ✔️ `c` is a live chunk with an initialized header
+            if unsafe { (*c.as_ptr()).free } == N {
+                match prev {
+                    None    => self.free.set(next),
+                    // SAFETY: ✔️ `p` is on the free list, ergo it's unallocated and stores the next free slot
+                    Some(p) => unsafe { (*p.as_ptr()).next = next },
+                }
+            } else {
+                prev = Some(s);
+            }
+            slot = next;
+        }
+
+        // Release empty chunks
+        let mut released = 0;
+        let mut prev : Option<NonNull<Chunk<A, B, N>>> = None;
+        let mut chunk = self.chunks.get();
+        while let Some(c) = chunk {
+            // SAFETY: ✔️ all chunks on `self.chunks` are live with initialized headers
+            let (next, free) = unsafe { ((*c.as_ptr()).next, (*c.as_ptr()).free) };
+            if free == N {
+                match prev {
+                    None    => self.chunks.set(next),
+                    // SAFETY: ✔️ all chunks on `self.chunks` are live with initialized headers
+                    Some(p) => unsafe { (*p.as_ptr()).next = next },
+                }
+                // SAFETY: ✔️ `c` was allocated by `self.backing` with `CHUNK_LAYOUT`, and none of its slots are allocated or on the free list anymore
+                unsafe { self.backing.free(c.cast(), Self::CHUNK_LAYOUT) };
+                released += 1;
+            } else {
+                prev = Some(c);
+            }
+            chunk = next;
+        }
+        released
+    }
+
+    /// Allocate a new chunk from `self.backing`, and link all of it's slots into `self.free`.
+    fn grow(&self) -> Result<(), ()> {
+        let chunk = self.backing.alloc_uninit(Self::CHUNK_LAYOUT).map_err(|_| ())?.cast::<Chunk<A, B, N>>();
+        let chunk = chunk.as_ptr();
+        // SAFETY: ✔️ `chunk` was just allocated with `CHUNK_LAYOUT`, so it's valid for writes of the header
+        unsafe {
+            addr_of_mut!((*chunk).next).write(self.chunks.get());
+            addr_of_mut!((*chunk).free).write(0);
+        }
+        // SAFETY: ✔️ `chunk` is nonnull (came from a `NonNull`)
+        self.chunks.set(Some(unsafe { NonNull::new_unchecked(chunk) }));
+
+        for index in (0 .. N).rev() {
+            // SAFETY: ✔️ `index` is in bounds of the freshly allocated `slots`
+            let slot : *mut Slot<B> = unsafe { addr_of_mut!((*chunk).slots).cast::<Element<A, B>>().add(index).cast() };
+            // SAFETY: ✔️ `slot` is unallocated, and `Slot` guarantees room for a properly aligned pointer
+            unsafe { addr_of_mut!((*slot).next).write(self.free.get()) };
+            self.free.set(NonNull::new(slot));
+        }
+        Ok(())
+    }
+
+    /// Find the chunk containing `slot`, if any.
+    fn chunk_of(&self, slot: NonNull<Slot<B>>) -> Option<NonNull<Chunk<A, B, N>>> {
+        let addr = slot.as_ptr() as usize;
+        let mut chunk = self.chunks.get();
+        while let Some(c) = chunk {
+            let start = c.as_ptr() as usize;
+            let end = start + core::mem::size_of::<[Element<A, B>; N]>();
+            if (start .. end).contains(&addr) { return Some(c) }
+            // SAFETY: ✔️ all chunks on `self.chunks` are live with initialized headers
+            chunk = unsafe { (*c.as_ptr()).next };
+        }
+        None
+    }
+}
+
+impl<const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free + Default> Default for PoolFreeList<A, B, N, Backing> where [(); A] : ValidAlignLessThan1GiB {
+    fn default() -> Self { Self::new() }
+}
+
+impl<const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free> Debug for PoolFreeList<A, B, N, Backing> where [(); A] : ValidAlignLessThan1GiB {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        #[cfg(    debug_assertions )] return write!(f, "PoolFreeList<{A}, {B}, {N}> {{ chunks: {}, outstanding_allocs: {} }}", self.chunks(), self.outstanding_allocs.get());
+        #[cfg(not(debug_assertions))] return write!(f, "PoolFreeList<{A}, {B}, {N}> {{ chunks: {}, outstanding_allocs: ?? }}", self.chunks());
+    }
+}
+
+impl<const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free> Drop for PoolFreeList<A, B, N, Backing> where [(); A] : ValidAlignLessThan1GiB {
+    fn drop(&mut self) {
+        // Outstanding allocations are about to dangle - e.g. perhaps `ABox::into_inner` outlived the pool.
+        #[cfg(debug_assertions)] assert_eq!(0, self.outstanding_allocs.get(), "allocator::simple::PoolFreeList has outstanding allocations");
+
+        let mut chunk = self.chunks.take();
+        while let Some(c) = chunk {
+            // SAFETY: ✔️ all chunks on `self.chunks` are live with initialized headers
+            chunk = unsafe { (*c.as_ptr()).next };
+            // SAFETY: ✔️ `c` was allocated by `self.backing` with `CHUNK_LAYOUT`
+            unsafe { self.backing.free(c.cast(), Self::CHUNK_LAYOUT) };
+        }
+    }
+}
+
+
+
+// meta::*
+
+impl<const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free> Meta for &'_ PoolFreeList<A, B, N, Backing> where [(); A] : ValidAlignLessThan1GiB {
+    type Error                  = ();
+    const MAX_ALIGN : Alignment = Alignment::constant(A);
+    const MAX_SIZE  : usize     = B;
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl<const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free> ZstSupported for &'_ PoolFreeList<A, B, N, Backing> where [(); A] : ValidAlignLessThan1GiB {}
+
+// SAFETY: ✔️ every allocation is a slot of one of `self.chunks`
+unsafe impl<const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free> Owns for PoolFreeList<A, B, N, Backing> where [(); A] : ValidAlignLessThan1GiB {
+    fn owns(&self, ptr: AllocNN) -> bool { self.chunk_of(ptr.cast()).is_some() }
+}
+
+
+
+// thin::*
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ if `alloc_uninit` succeeds:
+//  • We must return a pointer to at least `size` bytes.  As all slots are only `B` bytes long (or larger, never smaller, thanks to `Slot`), we reject `size` above that [1].
+//  • Allocations should have at least alignment `min(MAX_ALIGN, ...)`.  All slots have alignment `A` = `MAX_ALIGN` via `Element`'s definition including `AlignN` [2].
+//  • The slot should not have already been allocated - it's popped from the free list [3].
+//  • The allocations should remain valid for the lifetime of `Self`.  Chunks are only freed on drop, or if they contain no allocations.  Ergo, we must implement this on a *reference* to PoolFreeList, not a value of it.
+//
+unsafe impl<const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free> thin::Alloc for &'_ PoolFreeList<A, B, N, Backing> where [(); A] : ValidAlignLessThan1GiB {
+    fn alloc_uninit(&self, size: usize) -> Result<AllocNN, Self::Error> {
+        if size > B { return Err(()) } // [1]
+        if self.free.get().is_none() { self.grow()? }
+        let slot = self.free.get().ok_or(())?; // [3]
+        // SAFETY: ✔️ `slot` is on the free list, ergo it's unallocated and stores the next free slot
+        self.free.set(unsafe { (*slot.as_ptr()).next });
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+        Ok(slot.cast())
+    }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ `free` should be safe to call on any allocated `ptr` belonging to `self`
+//
+unsafe impl<const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free> thin::Free for &'_ PoolFreeList<A, B, N, Backing> where [(); A] : ValidAlignLessThan1GiB {
+    unsafe fn free(&self, ptr: AllocNN) {
+        let slot = ptr.cast::<Slot<B>>();
+        if cfg!(debug_assertions) && self.chunk_of(slot).is_none() { bug::ub::invalid_ptr_for_allocator(ptr) }
+        // SAFETY: ✔️ `ptr` belongs to `self` per `thin::Free::free`'s documented safety precondition, is no longer allocated, and `Slot` guarantees room for a properly aligned pointer
+        unsafe { (*slot.as_ptr()).next = self.free.get() };
+        self.free.set(Some(slot));
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() - 1);
+    }
+}
+
+// SAFETY: ✔️ all thin::* impls intercompatible with each other
+// SAFETY: ✔️ if `realloc_uninit` succeeds with defined behavior:
+//  • We must return a pointer to at least `size` bytes.  As all slots are at least `B` bytes long, we reject `size` above that [4].
+//  • Allocations should have at least alignment `min(MAX_ALIGN, ...)`.  All slots have the same alignment, so returning `ptr` again provides the same guarantee [5].
+//
+unsafe impl<const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free> thin::Realloc for &'_ PoolFreeList<A, B, N, Backing> where [(); A] : ValidAlignLessThan1GiB {
+    const CAN_REALLOC_ZEROED : bool = false;
+
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        if cfg!(debug_assertions) && self.chunk_of(ptr.cast()).is_none() { bug::ub::invalid_ptr_for_allocator(ptr) }
+        if new_size > B { return Err(()) } // [4]
+        Ok(ptr) // [5]
+    }
+
+    unsafe fn realloc_zeroed(&self, ptr: AllocNN, new_size: usize) -> Result<AllocNN, Self::Error> {
+        // SAFETY: ✔️ trivially safe - always failing is allowed
+        let _ = (ptr, new_size);
+        Err(())
+    }
+}
+
+#[no_implicit_prelude] mod cleanroom {
+    use crate::{impls, fat};
+    use super::{PoolFreeList, ValidAlignLessThan1GiB};
+
+    impls! {
+        unsafe impl['a, const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free] ialloc::fat::Alloc      for &'a PoolFreeList<A, B, N, Backing> where [[(); A] : ValidAlignLessThan1GiB] => ialloc::thin::Alloc;
+        unsafe impl['a, const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free] ialloc::fat::Realloc    for &'a PoolFreeList<A, B, N, Backing> where [[(); A] : ValidAlignLessThan1GiB] => ialloc::thin::Realloc;
+        unsafe impl['a, const A: usize, const B: usize, const N: usize, Backing: fat::Alloc + fat::Free] ialloc::fat::Free       for &'a PoolFreeList<A, B, N, Backing> where [[(); A] : ValidAlignLessThan1GiB] => ialloc::thin::Free;
+    }
+}
+
+
+
+type Element<const A : usize, const B : usize> = UnsafeCell<MaybeUninit<AlignN<A, Slot<B>>>>; // [2]
+
+/// Either `B` bytes of allocated data, or the next free slot.
+#[allow(dead_code)] // `data` is never read through - only used for size
+#[repr(C)] union Slot<const B: usize> { data: [u8; B], next: Option<NonNull<Slot<B>>> }
+
+#[repr(C)] struct Chunk<const A: usize, const B: usize, const N: usize> where [(); A] : ValidAlignLessThan1GiB {
+    slots:  [Element<A, B>; N],
+    next:   Option<NonNull<Chunk<A, B, N>>>,
+    free:   usize, // scratch space for `release_empty_chunks`
+}
+
+
+
+#[cfg(all(test, feature = "alloc"))] mod backing_global {
+    use crate::allocator::alloc::Global;
+    use super::{thin, fat, PoolFreeList};
+
+    #[test] fn test_quick() {
+        use crate::boxed::ABox;
+
+        let pool = PoolFreeList::<4, 4, 64, Global>::new();
+        let mut next = 0_u32;
+        for _ in 0 .. 10 {
+            assert!(ABox::try_new_in([0u8; 16], &pool).is_err(), "element too big to fit in pool");
+            let _integers = [(); 1024].map(|_| {
+                next += 1;
+                ABox::try_new_in(next, &pool).unwrap()
+            });
+            assert_eq!(1024/64, pool.chunks());
+        }
+        assert_eq!(1024/64, pool.release_empty_chunks());
+        assert_eq!(0, pool.chunks());
+    }
+
+    #[test] fn release_empty_chunks() {
+        use crate::boxed::ABox;
+
+        let pool = PoolFreeList::<8, 8, 4, Global>::new();
+        let boxes = [(); 16].map(|_| ABox::try_new_in(0u64, &pool).unwrap());
+        assert_eq!(4, pool.chunks());
+        assert_eq!(0, pool.release_empty_chunks());
+
+        let [a, b, c, d, e, f, g, h, i, j, k, l, m, n, o, p] = boxes;
+        drop((e, f, g, h, m, n, o, p)); // 2nd and 4th chunks now empty
+        assert_eq!(2, pool.release_empty_chunks());
+        assert_eq!(2, pool.chunks());
+
+        drop((b, c, k));
+        let more = [(); 3].map(|_| ABox::try_new_in(1u64, &pool).unwrap()); // reuses the 3 free slots of the remaining chunks
+        assert_eq!(2, pool.chunks());
+        drop((a, d, i, j, l, more));
+        assert_eq!(2, pool.release_empty_chunks());
+        assert_eq!(0, pool.chunks());
+    }
+
+    #[test] fn thin_alignment()         { thin::test::alignment(&PoolFreeList::<8, 8, 64, Global>::new()) }
+    #[test] fn thin_edge_case_sizes()   { thin::test::edge_case_sizes(&PoolFreeList::<8, 8, 64, Global>::new()) }
+    #[test] fn thin_nullable()          { thin::test::nullable(&PoolFreeList::<8, 8, 64, Global>::new()) }
+    #[test] fn thin_uninit()            { unsafe { thin::test::uninit_alloc_unsound(&PoolFreeList::<8, 8, 64, Global>::new()) } }
+    #[test] fn thin_uninit_realloc()    { thin::test::uninit_realloc(&PoolFreeList::<8, 8, 64, Global>::new()) }
+    #[test] fn thin_zeroed()            { thin::test::zeroed_alloc(&PoolFreeList::<8, 8, 64, Global>::new()) }
+    #[test] fn thin_zeroed_realloc()    { thin::test::zeroed_realloc(&PoolFreeList::<8, 8, 64, Global>::new()) }
+    #[test] fn thin_zst_support()       { thin::test::zst_supported_accurate(&PoolFreeList::<8, 8, 64, Global>::new()) }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(&PoolFreeList::<8, 8, 64, Global>::new()) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(&PoolFreeList::<8, 8, 64, Global>::new()) }
+    #[test] fn fat_uninit()             { unsafe { fat::test::uninit_alloc_unsound(&PoolFreeList::<8, 8, 64, Global>::new()) } }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(&PoolFreeList::<8, 8, 64, Global>::new()) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(&PoolFreeList::<8, 8, 64, Global>::new()) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(&PoolFreeList::<8, 8, 64, Global>::new()) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(&PoolFreeList::<8, 8, 64, Global>::new()) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use crate::allocator::c::Malloc;
+    use super::{thin, fat, PoolFreeList};
+
+    #[test] fn thin_alignment()         { thin::test::alignment(&PoolFreeList::<8, 8, 64, Malloc>::new()) }
+    #[test] fn thin_uninit_realloc()    { thin::test::uninit_realloc(&PoolFreeList::<8, 8, 64, Malloc>::new()) }
+    #[test] fn fat_alignment()          { fat::test::alignment(&PoolFreeList::<8, 8, 64, Malloc>::new()) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(&PoolFreeList::<8, 8, 64, Malloc>::new()) }
+}
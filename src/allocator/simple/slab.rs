@@ -0,0 +1,390 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::fmt::{self, Debug, Formatter};
+use core::mem::{align_of, size_of, MaybeUninit};
+use core::ptr::{NonNull, null_mut};
+
+
+
+/// Allocate small requests from per-size-class slabs, and larger requests directly from a `Backing` allocator
+///
+/// ## Size Classes
+///
+/// Requests are rounded up to the next power of two, from [`MIN_CLASS`](Self::MIN_CLASS) (8 bytes by default) to [`MAX_CLASS`](Self::MAX_CLASS) (2 KiB by default) inclusive.
+/// Each size class allocates pages of [`page_size(class)`](Self::page_size) bytes from `Backing`, and carves them into equally sized slots.
+/// Slots are aligned to their size (limited by what `Backing` can align pages to), so e.g. an 8 byte `u64` never shares a slot-sized hole with a 64 byte type.
+///
+/// Requests larger (or more aligned) than the biggest size class are passed straight through to `Backing`.
+///
+/// ## Allocation Scaling Behavior
+///
+/// Allocating, freeing, and reallocating within a size class is constant time, except when a new page must be allocated.
+/// Unallocated slots are (ab)used as per-class intrusive linked lists of free slots.
+/// Pages are only returned to `Backing` when the slab is dropped.
+///
+/// ## Generic Parameters
+/// *   `Backing` - the [`fat::Alloc`] + [`fat::Free`] allocator pages and large allocations are allocated from (e.g. [`alloc::Global`](crate::allocator::alloc::Global), [`c::Malloc`](crate::allocator::c::Malloc), or a reference to an [`Arena`](super::Arena))
+/// *   `MIN` - the smallest size class in bytes (must be a power of two, and at least pointer-sized)
+/// *   `CLASSES` - the number of size classes, each twice the size of the last (must be at least 1)
+/// *   `PAGE` - the minimum size of pages allocated from `Backing` in bytes (must be a power of two)
+///
+/// ## Example
+/// ```rust
+/// # use ialloc::allocator::{alloc::Global, simple::Slab};
+/// // 16 B ..= 256 B size classes, carved out of 64 KiB pages
+/// let slab = Slab::<Global, 16, 5, 65536>::new();
+/// assert_eq!(Slab::<Global, 16, 5, 65536>::MAX_CLASS, 256);
+/// ```
+pub struct Slab<Backing: fat::Alloc + fat::Free, const MIN: usize = 8, const CLASSES: usize = 9, const PAGE: usize = 4096> {
+    backing:    Backing,
+    classes:    [Class; CLASSES],
+    #[cfg(debug_assertions)] outstanding_allocs: Cell<usize>,
+}
+
+impl<Backing: fat::Alloc + fat::Free, const MIN: usize, const CLASSES: usize, const PAGE: usize> Slab<Backing, MIN, CLASSES, PAGE> {
+    /// The smallest size class, in bytes.
+    pub const MIN_CLASS : usize = MIN;
+
+    /// The largest size class, in bytes.  Larger allocations are passed through to `Backing`.
+    pub const MAX_CLASS : usize = MIN << (CLASSES - 1);
+
+    const ASSERT_LINK_FITS_MIN_CLASS : () = assert!(size_of::<Link>() <= MIN, "Slab<Backing, MIN, CLASSES, PAGE> requires MIN to be at least pointer-sized");
+    const ASSERT_VALID_CLASSES : () = {
+        assert!(MIN.is_power_of_two(), "Slab<Backing, MIN, CLASSES, PAGE> requires MIN to be a power of two");
+        assert!(CLASSES >= 1, "Slab<Backing, MIN, CLASSES, PAGE> requires at least 1 size class");
+        assert!(CLASSES - 1 < (usize::BITS - 4 - MIN.trailing_zeros()) as usize, "Slab<Backing, MIN, CLASSES, PAGE> has size classes too large to allocate pages for");
+        assert!(PAGE.is_power_of_two() && PAGE <= isize::MAX as usize, "Slab<Backing, MIN, CLASSES, PAGE> requires PAGE to be a power of two no larger than isize::MAX");
+    };
+
+    /// Create an empty slab, allocating pages from `Backing::default()` as necessary.
+    pub fn new() -> Self where Backing : Default { Self::new_in(Backing::default()) }
+
+    /// Create an empty slab, allocating pages from `backing` as necessary.
+    pub fn new_in(backing: Backing) -> Self {
+        let _ = Self::ASSERT_LINK_FITS_MIN_CLASS;
+        let _ = Self::ASSERT_VALID_CLASSES;
+        Self {
+            backing,
+            classes: [(); CLASSES].map(|_| Class { pages: Cell::new(None), free: Cell::new(None) }),
+            #[cfg(debug_assertions)] outstanding_allocs: Cell::new(0),
+        }
+    }
+
+    /// The size of pages allocated from `Backing` for the size class of `class` bytes.
+    pub const fn page_size(class: usize) -> usize { if class * 8 > PAGE { class * 8 } else { PAGE } }
+
+    /// Number of pages currently allocated from `Backing`, across all size classes.
+    pub fn pages(&self) -> usize {
+        let mut n = 0;
+        for class in self.classes.iter() {
+            let mut page = class.pages.get();
+            while let Some(p) = page {
+                n += 1;
+                // SAFETY: ✔️ the first slot of every page on `class.pages` stores the next page
+                page = unsafe { (*p.as_ptr()).next() };
+            }
+        }
+        n
+    }
+
+    /// The size class index for `layout`, or [`None`] if `layout` should be passed through to `Backing`.
+    fn class_of(layout: Layout) -> Option<usize> {
+        let size = layout.size().max(layout.align()).max(MIN).checked_next_power_of_two()?;
+        if size > Self::MAX_CLASS { return None }
+        let index = (size.trailing_zeros() - MIN.trailing_zeros()) as usize;
+        if layout.align() > Self::page_layout(index).align() { return None }
+        Some(index)
+    }
+
+    const fn class_size(index: usize) -> usize { MIN << index }
+
+    fn page_layout(index: usize) -> Layout {
+        let size = Self::class_size(index);
+        let align = size.min(Backing::MAX_ALIGN.as_usize()).max(align_of::<Link>());
+        // SAFETY: ✔️ `align` is the min/max of powers of two, and `page_size(size)` is a small multiple of `size` that fits per `ASSERT_VALID_CLASSES`
+        unsafe { Layout::from_size_align_unchecked(Self::page_size(size), align) }
+    }
+
+    /// Allocate a new page for size class `index` from `self.backing`, and link all but it's first slot into the class's free list.
+    fn grow(&self, index: usize) -> Result<(), ()> {
+        let class = &self.classes[index];
+        let size = Self::class_size(index);
+        let layout = Self::page_layout(index);
+        let page = self.backing.alloc_uninit(layout).map_err(|_| ())?.cast::<Link>();
+
+        // SAFETY: ✔️ `page` was just allocated with `layout`, and `Link` fits in the first slot per `ASSERT_LINK_FITS_MIN_CLASS` and `page_layout`'s alignment
+        unsafe { page.as_ptr().write(Link::new(class.pages.get())) };
+        class.pages.set(Some(page));
+
+        for offset in (size .. layout.size()).step_by(size).rev() {
+            // SAFETY: ✔️ `offset` is in bounds of the freshly allocated page
+            let slot = unsafe { page.as_ptr().cast::<u8>().add(offset).cast::<Link>() };
+            // SAFETY: ✔️ `slot` is unallocated, in bounds, and `Link` fits in (and is aligned by) every slot
+            unsafe { slot.write(Link::new(class.free.get())) };
+            class.free.set(NonNull::new(slot));
+        }
+        Ok(())
+    }
+}
+
+impl<Backing: fat::Alloc + fat::Free + Default, const MIN: usize, const CLASSES: usize, const PAGE: usize> Default for Slab<Backing, MIN, CLASSES, PAGE> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<Backing: fat::Alloc + fat::Free, const MIN: usize, const CLASSES: usize, const PAGE: usize> Debug for Slab<Backing, MIN, CLASSES, PAGE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        #[cfg(    debug_assertions )] return write!(f, "Slab {{ pages: {}, outstanding_allocs: {} }}", self.pages(), self.outstanding_allocs.get());
+        #[cfg(not(debug_assertions))] return write!(f, "Slab {{ pages: {}, outstanding_allocs: ?? }}", self.pages());
+    }
+}
+
+impl<Backing: fat::Alloc + fat::Free, const MIN: usize, const CLASSES: usize, const PAGE: usize> Drop for Slab<Backing, MIN, CLASSES, PAGE> {
+    fn drop(&mut self) {
+        // Outstanding allocations are about to dangle - e.g. perhaps `ABox::into_inner` outlived the slab.
+        #[cfg(debug_assertions)] assert_eq!(0, self.outstanding_allocs.get(), "allocator::simple::Slab has outstanding allocations");
+
+        for (index, class) in self.classes.iter().enumerate() {
+            let mut page = class.pages.take();
+            while let Some(p) = page {
+                // SAFETY: ✔️ the first slot of every page on `class.pages` stores the next page
+                page = unsafe { (*p.as_ptr()).next() };
+                // SAFETY: ✔️ `p` was allocated by `self.backing` with `page_layout(index)`
+                unsafe { self.backing.free(p.cast(), Self::page_layout(index)) };
+            }
+        }
+    }
+}
+
+
+
+// meta::*
+
+impl<Backing: fat::Alloc + fat::Free, const MIN: usize, const CLASSES: usize, const PAGE: usize> Meta for &'_ Slab<Backing, MIN, CLASSES, PAGE> {
+    type Error                  = ();
+    const MAX_ALIGN : Alignment = Backing::MAX_ALIGN;
+    const MAX_SIZE  : usize     = Backing::MAX_SIZE;
+    const ZST_SUPPORTED : bool  = Backing::ZST_SUPPORTED;
+}
+
+impl<Backing: fat::Alloc + fat::Free + ZstSupported, const MIN: usize, const CLASSES: usize, const PAGE: usize> ZstSupported for &'_ Slab<Backing, MIN, CLASSES, PAGE> {}
+
+// SAFETY: ✔️ every page, and every allocation passed through, was allocated from `self.backing`
+unsafe impl<Backing: fat::Alloc + fat::Free + Owns, const MIN: usize, const CLASSES: usize, const PAGE: usize> Owns for Slab<Backing, MIN, CLASSES, PAGE> {
+    fn owns(&self, ptr: AllocNN) -> bool { self.backing.owns(ptr) }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other:  `class_of(layout)` consistently routes `layout` to the same size class, or to `Backing`.
+// SAFETY: ✔️ if `alloc_uninit` succeeds, the allocation is either:
+//  • A slot popped off a size class's free list, at least `layout.size()` bytes, aligned to `layout.align()` per `class_of`.
+//  • An allocation from `Backing`, which made the same guarantees.
+// SAFETY: ✔️ pages are only freed on drop.  Ergo, we must implement this on a *reference* to Slab, not a value of it.
+//
+unsafe impl<Backing: fat::Alloc + fat::Free, const MIN: usize, const CLASSES: usize, const PAGE: usize> fat::Alloc for &'_ Slab<Backing, MIN, CLASSES, PAGE> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN, Self::Error> {
+        let alloc = match Slab::<Backing, MIN, CLASSES, PAGE>::class_of(layout) {
+            None => self.backing.alloc_uninit(layout).map_err(|_| ())?,
+            Some(index) => {
+                let class = &self.classes[index];
+                if class.free.get().is_none() { self.grow(index)? }
+                let slot = class.free.get().ok_or(())?;
+                // SAFETY: ✔️ `slot` is on the free list, ergo it's unallocated and stores the next free slot
+                class.free.set(unsafe { (*slot.as_ptr()).next() });
+                slot.cast()
+            },
+        };
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+        Ok(alloc)
+    }
+
+    fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> {
+        if Slab::<Backing, MIN, CLASSES, PAGE>::class_of(layout).is_some() {
+            let alloc = fat::Alloc::alloc_uninit(self, layout)?;
+            // SAFETY: ✔️ `alloc` was just allocated using `layout`
+            let all = unsafe { util::slice::from_raw_bytes_layout_mut(alloc, layout) };
+            all.fill(MaybeUninit::new(0u8));
+            Ok(alloc.cast())
+        } else {
+            let alloc = self.backing.alloc_zeroed(layout).map_err(|_| ())?;
+            #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+            Ok(alloc)
+        }
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ `free` should be safe to call on any allocated `ptr` belonging to `self` - `layout` routes it back to where it was allocated from
+//
+unsafe impl<Backing: fat::Alloc + fat::Free, const MIN: usize, const CLASSES: usize, const PAGE: usize> fat::Free for &'_ Slab<Backing, MIN, CLASSES, PAGE> {
+    unsafe fn free(&self, ptr: AllocNN, layout: Layout) {
+        match Slab::<Backing, MIN, CLASSES, PAGE>::class_of(layout) {
+            // SAFETY: ✔️ `ptr` was allocated by `self.backing` with `layout`, per `fat::Free::free`'s documented safety preconditions and `alloc_uninit`'s routing
+            None => unsafe { self.backing.free(ptr, layout) },
+            Some(index) => {
+                let class = &self.classes[index];
+                let slot = ptr.cast::<Link>();
+                // SAFETY: ✔️ `ptr` is a slot of this size class that is no longer allocated, and `Link` fits in (and is aligned by) every slot
+                unsafe { slot.as_ptr().write(Link::new(class.free.get())) };
+                class.free.set(Some(slot));
+            },
+        }
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() - 1);
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ if `realloc_uninit` succeeds:
+//  • Within the same size class, the slot already satisfies `new_layout` per `class_of`.
+//  • Between two passed through layouts, `Backing` makes the same guarantees.
+//  • Otherwise, we allocate anew, copy, and free.
+//
+unsafe impl<Backing: fat::Realloc, const MIN: usize, const CLASSES: usize, const PAGE: usize> fat::Realloc for &'_ Slab<Backing, MIN, CLASSES, PAGE> {
+    unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        match (Slab::<Backing, MIN, CLASSES, PAGE>::class_of(old_layout), Slab::<Backing, MIN, CLASSES, PAGE>::class_of(new_layout)) {
+            (Some(old), Some(new)) if old == new => Ok(ptr),
+            // SAFETY: ✔️ `ptr` was allocated by `self.backing` with `old_layout`, per `fat::Realloc::realloc_uninit`'s documented safety preconditions and `alloc_uninit`'s routing
+            (None, None) => unsafe { self.backing.realloc_uninit(ptr, old_layout, new_layout) }.map_err(|_| ()),
+            _ => {
+                // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+                unsafe { fat::realloc_by_copy(self, self, ptr, old_layout, new_layout) }
+            },
+        }
+    }
+
+    unsafe fn realloc_zeroed(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
+        if Slab::<Backing, MIN, CLASSES, PAGE>::class_of(old_layout).is_none() && Slab::<Backing, MIN, CLASSES, PAGE>::class_of(new_layout).is_none() {
+            // SAFETY: ✔️ `ptr` was allocated by `self.backing` with `old_layout`, per `fat::Realloc::realloc_zeroed`'s documented safety preconditions and `alloc_uninit`'s routing
+            return unsafe { self.backing.realloc_zeroed(ptr, old_layout, new_layout) }.map_err(|_| ());
+        }
+        // SAFETY: ✔️ realloc_uninit has same prereqs as realloc_zeroed
+        let alloc = unsafe { fat::Realloc::realloc_uninit(self, ptr, old_layout, new_layout) }?;
+        if old_layout.size() < new_layout.size() {
+            // SAFETY: ✔️ `alloc` was just (re)allocated using `new_layout`
+            let all = unsafe { util::slice::from_raw_bytes_layout_mut(alloc, new_layout) };
+            all[old_layout.size()..].fill(MaybeUninit::new(0u8));
+        }
+        Ok(alloc)
+    }
+}
+
+#[cfg(allocator_api = "1.50")] #[no_implicit_prelude] mod cleanroom {
+    use crate::{impls, fat};
+    use super::Slab;
+
+    impls! {
+        unsafe impl['a, Backing: fat::Realloc, const MIN: usize, const CLASSES: usize, const PAGE: usize] core::alloc::Allocator(unstable 1.50) for &'a Slab<Backing, MIN, CLASSES, PAGE> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+struct Class {
+    pages:  Cell<Option<NonNull<Link>>>,
+    free:   Cell<Option<NonNull<Link>>>,
+}
+
+/// Stored in the first slot of every page (linking pages), and in every free slot (linking free slots.)
+///
+/// The pointer is stored biased by [`Link::BIAS`] bytes, so that the first byte of a freed slot is never zero - not even for a null link.
+/// Otherwise a recycled slot could read back as zeroed memory, misleading callers into relying on `alloc_uninit` zeroing.
+struct Link { biased: *mut u8 }
+
+impl Link {
+    /// Offsets the first byte in memory of the stored pointer:  the low byte on little endian targets, the high byte on big endian targets.
+    const BIAS : usize = if cfg!(target_endian = "little") { 1 } else { 1 << (usize::BITS - 8) };
+
+    fn new(next: Option<NonNull<Link>>) -> Self { Self { biased: next.map_or(null_mut::<u8>(), |n| n.as_ptr().cast()).wrapping_add(Self::BIAS) } }
+    fn next(&self) -> Option<NonNull<Link>> { NonNull::new(self.biased.wrapping_sub(Self::BIAS).cast()) }
+}
+
+
+
+#[cfg(all(test, feature = "alloc"))] mod backing_global {
+    use crate::allocator::alloc::Global;
+    use crate::allocator::simple::Arena;
+    use super::{fat, Slab};
+
+    #[test] fn test_quick() {
+        use crate::boxed::ABox;
+
+        let slab = Slab::<Global>::new();
+        let mut next = 0_u32;
+        for _ in 0 .. 10 {
+            let _integers = [(); 1024].map(|_| { next += 1; ABox::try_new_in(next, &slab).unwrap() });
+            let _small    = [(); 64].map(|_| ABox::try_new_in([0u8; 100], &slab).unwrap());
+            let _large    = ABox::try_new_in([0u8; 4096], &slab).unwrap();
+            // 511 usable 8-byte slots per 4 KiB page, 31 usable 128-byte slots per 4 KiB page, no pages for large allocations
+            assert_eq!(3 + 3, slab.pages());
+        }
+    }
+
+    #[test] fn size_classes() {
+        use crate::fat::*;
+
+        let slab = Slab::<Global>::new();
+        let slab = &slab;
+        for (size, align, class) in [(0, 1, 8), (1, 1, 8), (8, 8, 8), (9, 1, 16), (1, 64, 64), (100, 4, 128), (2048, 8, 2048)] {
+            let layout = core::alloc::Layout::from_size_align(size, align).unwrap();
+            let a = slab.alloc_uninit(layout).unwrap();
+            let b = slab.alloc_uninit(layout).unwrap();
+            assert_eq!(0, a.as_ptr() as usize % class, "size {size} align {align} should be aligned to it's size class");
+            assert_eq!(class, (a.as_ptr() as usize).abs_diff(b.as_ptr() as usize), "consecutive allocations should be adjacent slots");
+            // SAFETY: ✔️ `a` and `b` were allocated from `slab` with `layout`
+            unsafe { slab.free(a, layout); slab.free(b, layout) };
+        }
+    }
+
+    #[test] fn custom_classes() {
+        use crate::fat::*;
+
+        let slab = Slab::<Global, 32, 3, 256>::new();
+        let slab = &slab;
+        assert_eq!(128, Slab::<Global, 32, 3, 256>::MAX_CLASS);
+        assert_eq!(1024, Slab::<Global, 32, 3, 256>::page_size(128));
+        for (size, class) in [(1, Some(32)), (33, Some(64)), (128, Some(128)), (129, None)] {
+            let layout = core::alloc::Layout::from_size_align(size, 1).unwrap();
+            let a = slab.alloc_uninit(layout).unwrap();
+            let b = slab.alloc_uninit(layout).unwrap();
+            if let Some(class) = class {
+                assert_eq!(class, (a.as_ptr() as usize).abs_diff(b.as_ptr() as usize), "consecutive allocations should be adjacent slots");
+            }
+            // SAFETY: ✔️ `a` and `b` were allocated from `slab` with `layout`
+            unsafe { slab.free(a, layout); slab.free(b, layout) };
+        }
+        assert_eq!(3, slab.pages(), "size 129 should've been passed through to Backing");
+    }
+
+    #[test] fn backing_arena() {
+        use crate::boxed::ABox;
+
+        let arena = Arena::<Global>::new();
+        let slab = Slab::<_>::new_in(&arena);
+        let _integers = [(); 1024].map(|_| ABox::try_new_in(0u64, &slab).unwrap());
+        let _large    = ABox::try_new_in([0u8; 4096], &slab).unwrap();
+    }
+
+    #[test] fn fat_alignment()          { fat::test::alignment(&Slab::<Global>::new()) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(&Slab::<Global>::new()) }
+    #[test] fn fat_uninit()             { unsafe { fat::test::uninit_alloc_unsound(&Slab::<Global>::new()) } }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(&Slab::<Global>::new()) }
+    #[test] fn fat_zeroed()             { fat::test::zeroed_alloc(&Slab::<Global>::new()) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(&Slab::<Global>::new()) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(&Slab::<Global>::new()) }
+}
+
+#[cfg(all(test, c89))] mod backing_malloc {
+    use crate::allocator::c::Malloc;
+    use super::{fat, Slab};
+
+    #[test] fn fat_alignment()          { fat::test::alignment(&Slab::<Malloc>::new()) }
+    #[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(&Slab::<Malloc>::new()) }
+    #[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(&Slab::<Malloc>::new()) }
+    #[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(&Slab::<Malloc>::new()) }
+    #[test] fn fat_zst_support()        { fat::test::zst_supported_conservative(&Slab::<Malloc>::new()) }
+}
@@ -0,0 +1,335 @@
+use crate::*;
+use crate::meta::*;
+
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::fmt::{self, Debug, Formatter};
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of, MaybeUninit};
+use core::ptr::NonNull;
+
+
+
+/// Stack-allocate from a slice of memory.  Allocations should be freed in reverse order of allocation (LIFO.)
+///
+/// Unlike [`Bump`](super::Bump), freeing the most recent allocation reclaims its memory for reuse.
+/// Each allocation is preceded by a small header recording the previous top of the stack.
+///
+/// ### Out-of-order frees
+/// *   (debug builds) are reported as bugs via panic.
+/// *   (release builds) are tolerated:  the allocation is marked as freed, and reclaimed once every allocation made after it has also been freed.
+pub struct Stack<'a> {
+    base:       NonNull<MaybeUninit<u8>>, // aligned to `align_of::<Header>()`
+    capacity:   usize,
+    top:        Cell<usize>,
+    last:       Cell<usize>, // offset of the most recent live allocation's `Header`, or `NONE`
+    #[cfg(debug_assertions)] outstanding_allocs: Cell<usize>,
+    phantom:    PhantomData<&'a mut [MaybeUninit<u8>]>,
+}
+
+#[repr(C)] #[derive(Clone, Copy)] struct Header {
+    /// `Stack::top` before this allocation was made
+    prev_top:   usize,
+    /// `Stack::last` before this allocation was made, `| FREED` if this allocation was freed out of order
+    prev_last:  usize,
+}
+
+const FREED : usize = 1;
+const NONE  : usize = !FREED;
+const _ : () = assert!(align_of::<Header>() > FREED, "`Header` offsets must leave the low bit free for `FREED`");
+
+// SAFETY: ✔️ `Stack` is semantically a `Cell<&'a mut [MaybeUninit<u8>]>`, which is `Send`
+unsafe impl Send for Stack<'_> {}
+
+impl<'a> Debug for Stack<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        #[cfg(    debug_assertions )] return write!(f, "Stack {{ buffer: [...{} bytes], outstanding_allocs: {} }}", self.available(), self.outstanding_allocs.get());
+        #[cfg(not(debug_assertions))] return write!(f, "Stack {{ buffer: [...{} bytes], outstanding_allocs: ?? }}", self.available());
+    }
+}
+
+impl<'a> Stack<'a> {
+    pub fn from_array<const N: usize>(array: &'a mut MaybeUninit<[MaybeUninit<u8>; N]>) -> Self {
+        // SAFETY: ✔️ supposedly safe per <https://doc.rust-lang.org/core/mem/union.MaybeUninit.html#initializing-an-array-element-by-element>
+        let array : &mut [MaybeUninit<u8>; N] = unsafe { array.assume_init_mut() };
+        Self::new(&mut array[..])
+    }
+
+    pub fn new(buffer: &'a mut [MaybeUninit<u8>]) -> Self {
+        let skip = (buffer.as_ptr() as usize).wrapping_neg() % align_of::<Header>();
+        let buffer = buffer.get_mut(skip..).unwrap_or_default();
+        Self {
+            capacity:   buffer.len(),
+            base:       NonNull::from(buffer).cast(),
+            top:        Cell::new(0),
+            last:       Cell::new(NONE),
+            #[cfg(debug_assertions)] outstanding_allocs: Cell::new(0),
+            phantom:    PhantomData,
+        }
+    }
+
+    fn available(&self) -> usize { self.capacity - self.top.get() }
+
+    /// ### Safety
+    /// *   `offset` must be the offset of a `Header` previously written by [`fat::Alloc::alloc_uninit`]
+    unsafe fn header(&self, offset: usize) -> NonNull<Header> {
+        // SAFETY: ✔️ `offset` is in bounds and aligned per documented safety precondition
+        unsafe { NonNull::new_unchecked(self.base.as_ptr().add(offset).cast()) }
+    }
+
+    /// Get the offset of `ptr`'s `Header`, reporting bugs in debug builds.
+    fn header_offset_of(&self, ptr: AllocNN) -> usize {
+        let start = (ptr.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize);
+        let offset = start.wrapping_sub(size_of::<Header>());
+        if cfg!(debug_assertions) && (start > self.top.get() || start < size_of::<Header>() || offset % align_of::<Header>() != 0) { bug::ub::invalid_ptr_for_allocator(ptr) }
+        offset
+    }
+
+    /// ### Safety
+    /// *   `offset` must be the offset of a live allocation's `Header`
+    unsafe fn release(&self, offset: usize) {
+        if offset != self.last.get() {
+            // Not the most recent allocation:  defer reclaiming it until everything allocated after it has been freed.
+            // SAFETY: ✔️ `offset` is the offset of a live allocation's `Header` per documented safety precondition
+            let header = unsafe { self.header(offset).as_mut() };
+            header.prev_last |= FREED;
+            return;
+        }
+
+        let mut offset = offset;
+        loop {
+            // SAFETY: ✔️ `offset` is always the offset of the most recent allocation's `Header`
+            let header = unsafe { self.header(offset).read() };
+            self.top.set(header.prev_top);
+            self.last.set(header.prev_last & !FREED);
+            offset = self.last.get();
+            if offset == NONE { break }
+            // SAFETY: ✔️ `offset` is the offset of an earlier allocation's `Header`
+            if unsafe { self.header(offset).read() }.prev_last & FREED == 0 { break }
+        }
+    }
+}
+
+impl<'a> Drop for Stack<'a> {
+    fn drop(&mut self) {
+        // As `'a` is likely nonstatic, this has a pretty serious chance of being a soundness bug - e.g. perhaps `ABox::into_inner` outlived `Stack`.
+        #[cfg(debug_assertions)] assert_eq!(0, self.outstanding_allocs.get(), "allocator::simple::Stack has outstanding allocations");
+    }
+}
+
+
+
+// meta::*
+
+impl<'a> Meta for Stack<'a> {
+    type Error                  = ();
+    const MAX_ALIGN : Alignment = Alignment::MAX;
+    const MAX_SIZE  : usize     = usize::MAX;
+    const ZST_SUPPORTED : bool  = true;
+}
+
+impl ZstSupported for Stack<'_> {}
+
+// SAFETY: ✔️ ZSTs are dangling pointers that never touch the stack
+unsafe impl ZstInfalliable for Stack<'_> {}
+
+// SAFETY: ✔️ every non-zero-sized allocation lies within the buffer
+unsafe impl Owns for Stack<'_> {
+    fn owns(&self, ptr: AllocNN) -> bool { (ptr.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize) < self.capacity }
+}
+
+
+
+// fat::*
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ allocations are carved from `buffer` above `top`, which no live allocation overlaps
+//
+unsafe impl<'a> fat::Alloc for Stack<'a> {
+    fn alloc_uninit(&self, layout: Layout) -> Result<crate::AllocNN, Self::Error> {
+        let size = layout.size();
+
+        let _ = Alignment::from(layout); // XXX: possibly hint to compiler that layout is nonzero
+        if size == 0 {
+            #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+            return Ok(crate::util::nn::dangling(layout));
+        }
+
+        // `base` is aligned for `Header`, so aligning the payload to at least that keeps the `Header` right before it aligned too.
+        let top = self.top.get();
+        let align = layout.align().max(align_of::<Header>());
+        let align_mask = align.wrapping_sub(1);
+        let header_end = top.wrapping_add(align_of::<Header>() - 1) & !(align_of::<Header>() - 1);
+        let header_end = header_end.saturating_add(size_of::<Header>());
+        let misalign = align_mask & (self.base.as_ptr() as usize).wrapping_add(header_end);
+        let skip = align.wrapping_sub(misalign) & align_mask;
+        let start = header_end.saturating_add(skip);
+        let end = start.saturating_add(size);
+        if end > self.capacity { return Err(()) } // ≈ OOM
+
+        let offset = start - size_of::<Header>();
+        // SAFETY: ✔️ `offset .. start` is within `buffer`, aligned for `Header`, and past `top` (unused by any live allocation)
+        unsafe { self.header(offset).write(Header { prev_top: top, prev_last: self.last.get() }) };
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() + 1);
+        self.top.set(end);
+        self.last.set(offset);
+        // SAFETY: ✔️ `start < end <= capacity`, so this stays within `buffer`
+        Ok(unsafe { NonNull::new_unchecked(self.base.as_ptr().add(start)) })
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ memory is only reclaimed once it and every allocation made after it has been freed
+//
+unsafe impl<'a> fat::Free for Stack<'a> {
+    unsafe fn free(&self, ptr: crate::AllocNN, layout: Layout) {
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() - 1);
+        if layout.size() == 0 { return }
+
+        let offset = self.header_offset_of(ptr);
+        // SAFETY: ✔️ `ptr` belongs to `self` per `fat::Free::free`'s documented safety precondition
+        if cfg!(debug_assertions) && unsafe { self.header(offset).read() }.prev_last & FREED != 0 { bug::ub::freed_ptr_for_allocator(ptr) }
+        if cfg!(debug_assertions) && offset != self.last.get() { bug::ub::out_of_order_free_for_allocator(ptr) }
+        // SAFETY: ✔️ `ptr` is a live allocation of `self` per `fat::Free::free`'s documented safety precondition
+        unsafe { self.release(offset) };
+    }
+}
+
+// SAFETY: ✔️ all fat::* impls intercompatible with each other
+// SAFETY: ✔️ only the most recent allocation is resized in place, as nothing lies above it
+//
+unsafe impl<'a> fat::Realloc for Stack<'a> {
+    unsafe fn realloc_uninit(&self, ptr: crate::AllocNN, old_layout: Layout, new_layout: Layout) -> Result<crate::AllocNN, Self::Error> {
+        if old_layout == new_layout { return Ok(ptr) }
+
+        // In-place fast path:  if `ptr` was the most recent allocation, and is already sufficiently aligned, just move the top of the stack.
+        // ZSTs are dangling, and thus never the most recent allocation.
+        if old_layout.size() != 0 && new_layout.size() != 0 && self.header_offset_of(ptr) == self.last.get() && (ptr.as_ptr() as usize) % new_layout.align() == 0 {
+            let start = (ptr.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize);
+            // Growing in place requires strictly less memory than allocating anew past the end of `ptr`, so there's no point falling back on failure.
+            let end = start.checked_add(new_layout.size()).ok_or(())?;
+            if end > self.capacity { return Err(()) } // ≈ OOM
+            self.top.set(end);
+            return Ok(ptr);
+        }
+
+        let alloc = fat::Alloc::alloc_uninit(self, new_layout)?;
+        // SAFETY: ✔️ `ptr` is valid for `old_layout` by `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        // SAFETY: ✔️ `alloc` was just allocated using `new_layout`, and cannot overlap `ptr` (which is still allocated)
+        unsafe { fat::copy_prefix(ptr, old_layout, alloc, new_layout) };
+        #[cfg(debug_assertions)] self.outstanding_allocs.set(self.outstanding_allocs.get() - 1);
+        // `ptr` is now buried beneath `alloc` - this free is necessarily out of order, and thus deferred rather than reported.
+        // SAFETY: ✔️ `ptr` is a live allocation of `self` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        if old_layout.size() != 0 { unsafe { self.release(self.header_offset_of(ptr)) } }
+        Ok(alloc)
+    }
+}
+
+#[no_implicit_prelude] mod cleanroom {
+    use crate::impls;
+    use super::Stack;
+
+    impls! {
+        unsafe impl     core::alloc::GlobalAlloc for     Stack<'static> => ialloc::fat::Realloc;
+        unsafe impl['o] core::alloc::GlobalAlloc for &'o Stack<'static> => core::ops::Deref;
+    }
+
+    #[cfg(allocator_api = "1.50")] impls! {
+        unsafe impl['a] core::alloc::Allocator(unstable 1.50) for Stack<'a> => ialloc::fat::Realloc;
+    }
+}
+
+
+
+#[test] fn test_quick() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 4096]> = MaybeUninit::uninit();
+    let alloc = Stack::from_array(&mut buffer);
+    let capacity = alloc.available();
+    {
+        let _u32    = ABox::try_new_in(1u32, &alloc).unwrap();
+        let _u8a    = ABox::try_new_in(2u8,  &alloc).unwrap();
+        let _u8b    = ABox::try_new_in(3u8,  &alloc).unwrap();
+        let _i32    = ABox::try_new_in(5i32, &alloc).unwrap();
+        let _bool   = ABox::try_new_in(true, &alloc).unwrap();
+        // locals drop in reverse order of declaration:  LIFO
+    }
+    assert_eq!(capacity, alloc.available(), "freeing everything in reverse order should reclaim everything");
+
+    for _ in 0 .. 1000 {
+        let a = ABox::try_new_in([1u8; 1024], &alloc).unwrap();
+        let b = ABox::try_new_in([2u8; 1024], &alloc).unwrap();
+        drop(b);
+        drop(a);
+    }
+}
+
+#[test] fn reuse() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 256]> = MaybeUninit::uninit();
+    let alloc = Stack::from_array(&mut buffer);
+
+    let a = ABox::try_new_in([1u8; 16], &alloc).unwrap();
+    let b = ABox::try_new_in([2u8; 16], &alloc).unwrap();
+    let b_ptr = &*b as *const [u8; 16];
+    drop(b);
+    let c = ABox::try_new_in([3u8; 16], &alloc).unwrap();
+    assert_eq!(b_ptr, &*c as *const [u8; 16], "freed top of stack should be reused");
+    assert_eq!([1u8; 16], *a);
+}
+
+#[test] fn realloc_in_place() {
+    use crate::fat::*;
+
+    let mut buffer : MaybeUninit<[_; 4096]> = MaybeUninit::uninit();
+    let alloc = Stack::from_array(&mut buffer);
+    let capacity = alloc.available();
+    let l16  = Layout::from_size_align(16, 4).unwrap();
+    let l64  = Layout::from_size_align(64, 4).unwrap();
+    let l8   = Layout::from_size_align( 8, 4).unwrap();
+
+    let a = alloc.alloc_uninit(l16).unwrap();
+    // SAFETY: ✔️ `a` belongs to `alloc` and was allocated with `l16`
+    let a2 = unsafe { alloc.realloc_uninit(a, l16, l64) }.unwrap();
+    assert_eq!(a, a2, "most recent allocation should grow in place");
+
+    // SAFETY: ✔️ `a2` belongs to `alloc` and was reallocated with `l64`
+    let a3 = unsafe { alloc.realloc_uninit(a2, l64, l8) }.unwrap();
+    assert_eq!(a, a3, "most recent allocation should shrink in place");
+
+    let b = alloc.alloc_uninit(l8).unwrap();
+    // SAFETY: ✔️ `a3` belongs to `alloc` and was reallocated with `l8`
+    let a4 = unsafe { alloc.realloc_uninit(a3, l8, l16) }.unwrap();
+    assert_ne!(a, a4, "no longer the most recent allocation - should've moved");
+
+    // SAFETY: ✔️ `a4` and `b` belong to `alloc` and were (re)allocated with `l16` and `l8` respectively
+    unsafe { alloc.free(a4, l16) };
+    assert_ne!(capacity, alloc.available(), "`b` is still live");
+    // SAFETY: ✔️ `b` belongs to `alloc` and was allocated with `l8`
+    unsafe { alloc.free(b, l8) };
+    assert_eq!(capacity, alloc.available(), "freeing `b` should also reclaim the old `a` buried beneath it");
+}
+
+#[cfg(debug_assertions)]
+#[test] #[should_panic = "freed out of order"] fn out_of_order_free() {
+    use crate::boxed::ABox;
+
+    let mut buffer : MaybeUninit<[_; 256]> = MaybeUninit::uninit();
+    let alloc = core::mem::ManuallyDrop::new(Stack::from_array(&mut buffer)); // don't double panic in `Drop`
+    let a = ABox::try_new_in(1u32, &*alloc).unwrap();
+    let b = ABox::try_new_in(2u32, &*alloc).unwrap();
+    drop(a);
+    drop(b);
+}
+
+
+
+#[test] fn fat_alignment()          { fat::test::alignment(Stack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_edge_case_sizes()    { fat::test::edge_case_sizes(Stack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_uninit()             { unsafe { fat::test::uninit_alloc_unsound(Stack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) } }
+#[test] fn fat_uninit_realloc()     { fat::test::uninit_realloc(Stack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zeroed()             { fat::test::zeroed_alloc(Stack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zeroed_realloc()     { fat::test::zeroed_realloc(Stack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
+#[test] fn fat_zst_support()        { fat::test::zst_supported_accurate(Stack::from_array(&mut MaybeUninit::new([(); 131072].map(|_| MaybeUninit::<u8>::new(0xFF))))) }
@@ -2,7 +2,9 @@
 
 use crate::Alignment;
 
+use core::alloc::Layout;
 use core::ffi::c_void;
+use core::fmt::Display;
 use core::mem::MaybeUninit;
 use core::ptr::NonNull;
 
@@ -14,6 +16,11 @@ impl AsPtr for *mut                u8   { fn as_ptr(self) -> *mut c_void { self.
 impl AsPtr for NonNull<MaybeUninit<u8>> { fn as_ptr(self) -> *mut c_void { self.as_ptr().cast() } }
 impl AsPtr for NonNull<            u8 > { fn as_ptr(self) -> *mut c_void { self.as_ptr().cast() } }
 
+/// Report a `FrameRing` frame being recycled while allocations from it are still live (caught before it could cause Undefined Behavior)
+#[track_caller] #[inline(never)] pub fn recycled_frame_still_allocated(frame: usize, live: usize) -> ! {
+    panic!("bug: tried to recycle frame {frame}, but {live} allocations from it are still live");
+}
+
 /// Report bugs that indicate Undefined Behavior
 pub mod ub {
     use super::*;
@@ -27,6 +34,10 @@ pub mod ub {
         }
     }
 
+    #[track_caller] #[inline(never)] pub fn invalid_free_size_for_allocator(size: usize) -> ! {
+        panic!("bug: undefined behavior: tried to free an allocation of {size} bytes, but that's larger than this allocator could have allocated");
+    }
+
     #[track_caller] #[inline(never)] pub fn invalid_ptr_for_allocator(ptr: impl AsPtr) -> ! {
         let ptr = ptr.as_ptr();
         panic!("bug: undefined behavior: {ptr:?} doesn't belong to this allocator");
@@ -37,11 +48,35 @@ pub mod ub {
         panic!("bug: undefined behavior: {ptr:?} belongs to this allocator, but it was already freed");
     }
 
+    #[track_caller] #[inline(never)] pub fn mismatched_layout_for_allocator(ptr: impl AsPtr, allocated: Layout, passed: Layout) -> ! {
+        let ptr = ptr.as_ptr();
+        panic!("bug: undefined behavior: {ptr:?} belongs to this allocator, but was (re)allocated with {allocated:?} and then passed {passed:?}");
+    }
+
+    #[track_caller] #[inline(never)] pub fn written_after_free(ptr: impl AsPtr, layout: Layout, freed_at: impl Display) -> ! {
+        let ptr = ptr.as_ptr();
+        panic!("bug: undefined behavior: {ptr:?} ({layout:?}) was written to after being freed at {freed_at}");
+    }
+
+    #[track_caller] #[inline(never)] pub fn out_of_order_free_for_allocator(ptr: impl AsPtr) -> ! {
+        let ptr = ptr.as_ptr();
+        panic!("bug: undefined behavior: {ptr:?} belongs to this allocator, but was freed out of order (allocations must be freed in reverse order of allocation)");
+    }
+
     #[track_caller] #[inline(never)] pub fn invalid_zst_for_allocator(ptr: impl AsPtr) -> ! {
         let ptr = ptr.as_ptr();
         panic!("bug: undefined behavior: {ptr:?} doesn't belong to this allocator (ZST, but ZSTs are never allocated by this allocator)");
     }
 
+    #[track_caller] #[inline(never)] pub fn clobbered_guard_bytes(ptr: impl AsPtr, layout: Option<Layout>, side: &str) -> ! {
+        let ptr = ptr.as_ptr();
+        if let Some(layout) = layout {
+            panic!("bug: undefined behavior: the guard bytes {side} {ptr:?} ({layout:?}) were overwritten (typically this means a buffer overrun or underrun)");
+        } else {
+            panic!("bug: undefined behavior: the guard bytes {side} {ptr:?} were overwritten, as was the size of the allocation (typically this means a buffer underrun)");
+        }
+    }
+
     #[track_caller] #[inline(never)] pub fn free_failed(ptr: impl AsPtr) -> ! {
         let ptr = ptr.as_ptr();
         panic!("bug: undefined behavior: freeing {ptr:?} failed (typically this means the pointer didn't belong to the allocator, or there was heap corruption)");
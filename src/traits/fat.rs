@@ -103,21 +103,8 @@ pub unsafe trait Realloc : Alloc + Free {
     /// *   `old_layout` must exactly match the [`Layout`] last used to successfully (re)allocate `ptr`
     unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> {
         if old_layout == new_layout { return Ok(ptr) }
-        let alloc = self.alloc_uninit(new_layout)?;
-        {
-            // SAFETY: ✔️ `ptr` is valid for `old_layout` by `fat::Realloc::realloc_uninit`'s documented safety preconditions
-            // SAFETY: ✔️ `alloc` was just allocated using `new_layout`
-            #![allow(clippy::undocumented_unsafe_blocks)]
-
-            let old : &    [MaybeUninit<u8>] = unsafe { util::slice::from_raw_bytes_layout    (ptr,   old_layout) };
-            let new : &mut [MaybeUninit<u8>] = unsafe { util::slice::from_raw_bytes_layout_mut(alloc, new_layout) };
-            let n = old.len().min(new.len());
-            new[..n].copy_from_slice(&old[..n]);
-        }
-        // SAFETY: ✔️ `ptr` is valid for `old_layout` by `fat::Realloc::realloc_uninit`'s documented safety preconditions
-        // SAFETY: ✔️ `ptr` belongs to `self` by `fat::Realloc::realloc_uninit`'s documented safety preconditions
-        unsafe { self.free(ptr, old_layout) };
-        Ok(alloc)
+        // SAFETY: ✔️ `ptr` belongs to `self` and was allocated with `old_layout` per `fat::Realloc::realloc_uninit`'s documented safety preconditions
+        unsafe { realloc_by_copy(self, self, ptr, old_layout, new_layout) }
     }
 
     /// Reallocate an existing allocation, `ptr`, belonging to `self`.
@@ -149,19 +136,54 @@ pub unsafe trait Realloc : Alloc + Free {
 
 #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ same trait, same prereqs
 unsafe impl<'a, A: Alloc> Alloc for &'a A {
-    fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN,  Self::Error> { A::alloc_uninit(self, layout) }
-    fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> { A::alloc_zeroed(self, layout) }
+    #[track_caller] fn alloc_uninit(&self, layout: Layout) -> Result<AllocNN,  Self::Error> { A::alloc_uninit(self, layout) }
+    #[track_caller] fn alloc_zeroed(&self, layout: Layout) -> Result<AllocNN0, Self::Error> { A::alloc_zeroed(self, layout) }
 }
 
 #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ same trait, same prereqs
 unsafe impl<'a, A: Free> Free for &'a A {
-    unsafe fn free(&self, ptr: AllocNN, layout: Layout) { unsafe { A::free(self, ptr, layout) } }
+    #[track_caller] unsafe fn free(&self, ptr: AllocNN, layout: Layout) { unsafe { A::free(self, ptr, layout) } }
 }
 
 #[allow(clippy::undocumented_unsafe_blocks)] // SAFETY: ✔️ same trait, same prereqs
 unsafe impl<'a, A: Realloc> Realloc for &'a A {
-    unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> { unsafe { A::realloc_uninit(self, ptr, old_layout, new_layout) } }
-    unsafe fn realloc_zeroed(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> { unsafe { A::realloc_zeroed(self, ptr, old_layout, new_layout) } }
+    #[track_caller] unsafe fn realloc_uninit(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> { unsafe { A::realloc_uninit(self, ptr, old_layout, new_layout) } }
+    #[track_caller] unsafe fn realloc_zeroed(&self, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, Self::Error> { unsafe { A::realloc_zeroed(self, ptr, old_layout, new_layout) } }
+}
+
+
+
+/// Move `ptr` to a new allocation:  allocate `new_layout` from `to`, copy the common prefix, then free `ptr` to `from`.
+///
+/// This is [`Realloc::realloc_uninit`]'s default implementation (with `from` and `to` both `self`), for use by implementations that only sometimes reallocate in place.
+///
+/// ### Safety
+/// *   `ptr` must belong to `from`
+/// *   `ptr` will no longer be accessible after a succesful realloc (`realloc_by_copy` returns <code>[Ok]\(...\)</code>)
+/// *   `old_layout` must exactly match the [`Layout`] last used to successfully (re)allocate `ptr`
+#[track_caller] pub(crate) unsafe fn realloc_by_copy<F: Free + ?Sized, T: Alloc + ?Sized>(from: &F, to: &T, ptr: AllocNN, old_layout: Layout, new_layout: Layout) -> Result<AllocNN, T::Error> {
+    let alloc = to.alloc_uninit(new_layout)?;
+    // SAFETY: ✔️ `ptr` is valid for `old_layout` per fn's documented safety preconditions
+    // SAFETY: ✔️ `alloc` was just allocated using `new_layout`, and cannot overlap `ptr` (which is still allocated)
+    unsafe { copy_prefix(ptr, old_layout, alloc, new_layout) };
+    // SAFETY: ✔️ `ptr` belongs to `from` and was allocated with `old_layout` per fn's documented safety preconditions
+    unsafe { from.free(ptr, old_layout) };
+    Ok(alloc)
+}
+
+/// Copy the first `min(src_layout.size(), dst_layout.size())` bytes of `src` to `dst`.
+///
+/// ### Safety
+/// *   `src` must be valid for reads of `src_layout.size()` bytes
+/// *   `dst` must be valid for writes of `dst_layout.size()` bytes, and must not overlap `src`
+pub(crate) unsafe fn copy_prefix(src: AllocNN, src_layout: Layout, dst: AllocNN, dst_layout: Layout) {
+    // SAFETY: ✔️ per fn's documented safety preconditions
+    #![allow(clippy::undocumented_unsafe_blocks)]
+
+    let old : &    [MaybeUninit<u8>] = unsafe { util::slice::from_raw_bytes_layout    (src, src_layout) };
+    let new : &mut [MaybeUninit<u8>] = unsafe { util::slice::from_raw_bytes_layout_mut(dst, dst_layout) };
+    let n = old.len().min(new.len());
+    new[..n].copy_from_slice(&old[..n]);
 }
 
 
@@ -215,6 +237,9 @@ pub mod test {
     }
 
     /// Check edge cases near 2 GiB, 4 GiB, usize::MAX/2, and usize::MAX watermarks.
+    ///
+    /// Unsuitable for allocators which write every byte they allocate or free (e.g. `debug::Poison`, `debug::Quarantine`, `adapt::ZeroOnFree`):
+    /// they'd commit and touch every page of the multi-GiB allocations this makes, so their tests skip this.
     pub fn edge_case_sizes<A: Alloc + Free>(allocator: A) {
         let boundaries = if cfg!(target_pointer_width = "64") {
             &[0, (u32::MAX/2) as usize, (u32::MAX  ) as usize, usize::MAX/2, usize::MAX][..]
@@ -126,3 +126,23 @@ pub unsafe trait ZstInfalliable : ZstSupported {}
 /// *   [`vec::AVec::into_raw_parts`] (use [`vec::AVec::into_raw_parts_with_allocator`] instead)
 #[allow(rustdoc::broken_intra_doc_links)] // FIXME: remove
 pub unsafe trait Stateless : Default {}
+
+
+
+/// Allocator can tell if a pointer belongs to it.
+///
+/// ### Safety
+/// [`owns`](Self::owns) must return `true` for every pointer returned by a non-zero-sized allocation from `self` (until it's freed), and `false` for every pointer belonging to another allocator.
+/// It may return `true` for pointers into memory managed by `self` that aren't currently allocated.
+/// Wrappers such as [`Fallback`](crate::allocator::adapt::Fallback) rely on this to free memory with the allocator that allocated it.
+///
+/// Zero-sized allocations are typically dangling, and thus may or may not be considered owned.
+pub unsafe trait Owns {
+    /// Returns `true` if `ptr` points into memory managed by `self`.
+    fn owns(&self, ptr: AllocNN) -> bool;
+}
+
+// SAFETY: ✔️ same trait, same prereqs
+unsafe impl<'a, A: Owns> Owns for &'a A {
+    fn owns(&self, ptr: AllocNN) -> bool { A::owns(self, ptr) }
+}
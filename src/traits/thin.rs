@@ -312,6 +312,8 @@ pub mod test {
     }
 
     /// Check edge cases near 2 GiB, 4 GiB, usize::MAX/2, and usize::MAX watermarks.
+    ///
+    /// Unsuitable for the same allocators as [`crate::fat::test::edge_case_sizes`].
     pub fn edge_case_sizes<A: Alloc + Free>(allocator: A) {
         let boundaries = if cfg!(target_pointer_width = "64") {
             &[0, (u32::MAX/2) as usize, (u32::MAX  ) as usize, usize::MAX/2, usize::MAX][..]